@@ -1,3 +1,5 @@
+use intcode::symbolic::{End, Symbolic, Var};
+use intcode::Intcode;
 use std::fs::File;
 use std::io::Read;
 
@@ -11,34 +13,57 @@ fn main() {
         .join("input.txt"),
     )
     .expect("unable to open input.txt");
-    let mut v = read_input(&mut file).expect("parse error");
+    let mut text = String::new();
+    file.read_to_string(&mut text)
+        .expect("unable to read input.txt");
+    let mut v = read_input(&mut text.as_bytes()).expect("parse error");
+    let program = intcode::read_input(&mut text.as_bytes()).expect("parse error");
 
     v[1] = 12;
     v[2] = 2;
     assert_eq!(run_program(&v), 10_566_835);
     println!("Output: {}", run_program(&v));
 
-    v[2] = 0;
-    let noun = (0..99)
-        .rev()
-        .find(|&noun| {
-            v[1] = noun;
-            run_program(&v) <= TARGET
-        })
-        .expect("no valid nouns found");
-
-    let verb = (0..99)
-        .rev()
-        .find(|&noun| {
-            v[2] = noun;
-            run_program(&v) == TARGET
-        })
-        .expect("no valid verbs found");
+    let (noun, verb) = solve(&program)
+        .or_else(|| search(&v))
+        .expect("no noun and verb found");
 
+    v[1] = noun;
+    v[2] = verb;
     assert_eq!(run_program(&v), TARGET);
     println!("Noun Verb: {}", noun * 100 + verb);
 }
 
+/// Address 0 ends up linear in the noun and verb, so solve for them. Only trusted when the
+/// program has a single way through that halts.
+fn solve(program: &[Intcode]) -> Option<(usize, usize)> {
+    let mut symbolic = Symbolic::new(program);
+    symbolic.unknown(1);
+    symbolic.unknown(2);
+    let paths = symbolic.explore();
+    let path = match &paths[..] {
+        [path] if path.end == End::Halted => path,
+        _ => return None,
+    };
+    let solution =
+        path.read(0)
+            .linear()?
+            .solve(TARGET as Intcode, &[Var::Cell(1), Var::Cell(2)], 0..=99)?;
+    Some((solution[0] as usize, solution[1] as usize))
+}
+
+/// Try every noun and verb
+fn search(v: &[usize]) -> Option<(usize, usize)> {
+    let mut v = v.to_vec();
+    (0..=99)
+        .flat_map(|noun| (0..=99).map(move |verb| (noun, verb)))
+        .find(|&(noun, verb)| {
+            v[1] = noun;
+            v[2] = verb;
+            run_program(&v) == TARGET
+        })
+}
+
 fn read_input<R: Read>(input: &mut R) -> Result<Vec<usize>, String> {
     let mut buffer = String::new();
     if let Err(msg) = input.read_to_string(&mut buffer) {
@@ -123,4 +148,48 @@ mod test {
             assert_eq!(run_ops(case.0, case.1), case.2);
         }
     }
+
+    #[test]
+    fn solvers() {
+        let path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("input.txt");
+        let text = std::fs::read_to_string(path).unwrap();
+        let v = read_input(&mut text.as_bytes()).unwrap();
+        let program = intcode::read_input(&mut text.as_bytes()).unwrap();
+        assert_eq!(solve(&program), Some((23, 47)));
+        assert_eq!(search(&v), Some((23, 47)));
+
+        // a fault is no answer, and neither is having no path at all
+        assert_eq!(solve(&[1, 0, 0, 0, 42]), None);
+        assert_eq!(solve(&[]), None);
+    }
+
+    #[test]
+    fn fuzz() {
+        use intcode::fuzz::{self, Case, Engine, Profile, Run};
+
+        // all day 2 reports is address 0 once the program halts
+        let at_zero = |value| Run {
+            outputs: vec![],
+            halted: true,
+            memory: vec![value],
+        };
+        let reference = |case: &Case| {
+            let run = fuzz::reference(case).filter(|run| run.halted)?;
+            Some(at_zero(run.memory.first().copied().unwrap_or(0)))
+        };
+        // out of range addresses are a panic rather than an answer
+        let day2 = |case: &Case| {
+            let program: Vec<_> = case.program.iter().map(|&w| w as usize).collect();
+            let value = fuzz::quietly(|| run_ops(&program, 0))?;
+            Some(at_zero(value as i64))
+        };
+
+        let engines = [
+            Engine::new("intcode", reference),
+            Engine::new("day 2", day2),
+        ];
+        let compared =
+            fuzz::fuzz(2, 2000, &Profile::day2(), &engines).unwrap_or_else(|d| panic!("{}", d));
+        assert!(compared > 200, "only {} cases compared", compared);
+    }
 }
@@ -220,4 +220,46 @@ mod test {
             );
         }
     }
+
+    #[test]
+    fn fuzz() {
+        use intcode::fuzz::{self, Case, Engine, Profile, Run, BUDGET};
+        use std::convert::TryFrom;
+
+        // day 5 reports the last output, or address 0 if there was none, once the program
+        // halts, and feeds the same input to every IN
+        let answer = |value| Run {
+            outputs: vec![value],
+            halted: true,
+            memory: vec![],
+        };
+        let reference = |case: &Case| {
+            let case = Case {
+                program: case.program.clone(),
+                inputs: vec![*case.inputs.first()?; BUDGET],
+            };
+            let run = fuzz::reference(&case).filter(|run| run.halted)?;
+            let value = run.outputs.last().or_else(|| run.memory.first());
+            Some(answer(value.copied().unwrap_or(0)))
+        };
+        // values outside i32 and out of range addresses are a panic rather than an answer
+        let day5 = |case: &Case| {
+            let program: Vec<_> = case
+                .program
+                .iter()
+                .map(|&w| i32::try_from(w).ok())
+                .collect::<Option<_>>()?;
+            let input = i32::try_from(*case.inputs.first()?).ok()?;
+            let value = fuzz::quietly(|| run_ops(&program, input, 0))?;
+            Some(answer(value.into()))
+        };
+
+        let engines = [
+            Engine::new("intcode", reference),
+            Engine::new("day 5", day5),
+        ];
+        let compared =
+            fuzz::fuzz(5, 1000, &Profile::day5(), &engines).unwrap_or_else(|d| panic!("{}", d));
+        assert!(compared > 200, "only {} cases compared", compared);
+    }
 }
@@ -0,0 +1,181 @@
+use intcode::network::{run_network, Outcome, Topology};
+use intcode::{run_program_n, Computer, Intcode};
+use rayon::prelude::*;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mode {
+    /// Each amplifier runs once, feeding the next
+    Series,
+    /// The last amplifier feeds the first until they all halt
+    Feedback,
+}
+
+pub struct AmplifierChain<'a> {
+    program: &'a [Intcode],
+    stages: usize,
+    mode: Mode,
+}
+
+impl<'a> AmplifierChain<'a> {
+    pub fn new(program: &'a [Intcode], stages: usize, mode: Mode) -> Self {
+        Self {
+            program,
+            stages,
+            mode,
+        }
+    }
+
+    /// The signal coming out of the chain, or `None` unless there is one phase per stage
+    pub fn signal(&self, phases: &[Intcode]) -> Option<Intcode> {
+        if phases.len() != self.stages {
+            return None;
+        }
+        match self.mode {
+            Mode::Series => Some(run_amplifier(self.program, 0, phases)),
+            Mode::Feedback => run_feedback(self.program, phases),
+        }
+    }
+
+    /// Try every assignment of distinct values from `phase_set` to the stages and return the
+    /// one giving the strongest signal
+    pub fn best(&self, phase_set: &[Intcode]) -> Option<(Vec<Intcode>, Intcode)> {
+        permutations(phase_set, self.stages)
+            .into_par_iter()
+            .filter_map(|phases| {
+                let signal = self.signal(&phases)?;
+                Some((phases, signal))
+            })
+            .max_by_key(|(_, signal)| *signal)
+    }
+}
+
+/// Every ordered selection of `count` distinct items
+fn permutations(items: &[Intcode], count: usize) -> Vec<Vec<Intcode>> {
+    if count == 0 {
+        return vec![vec![]];
+    }
+
+    let mut all = Vec::new();
+    for (idx, &first) in items.iter().enumerate() {
+        let rest: Vec<_> = items
+            .iter()
+            .enumerate()
+            .filter(|&(i, _)| i != idx)
+            .map(|(_, &item)| item)
+            .collect();
+        for mut tail in permutations(&rest, count - 1) {
+            tail.insert(0, first);
+            all.push(tail);
+        }
+    }
+    all
+}
+
+/// Run one amplifier per phase, all wired in a ring, and return the last signal sent back
+/// to the first amplifier, or `None` unless every amplifier halted
+fn run_feedback(opcodes: &[Intcode], phases: &[Intcode]) -> Option<Intcode> {
+    let amps = phases
+        .iter()
+        .enumerate()
+        .map(|(idx, &phase)| {
+            let mut amp = Computer::new(opcodes);
+            amp.input_add(phase);
+            if idx == 0 {
+                amp.input_add(0);
+            }
+            amp
+        })
+        .collect();
+    let report = run_network(amps, &Topology::Ring).ok()?;
+
+    if report.machines.iter().any(|m| m.outcome != Outcome::Halted) {
+        return None;
+    }
+    report.machines.last()?.outputs.last().copied()
+}
+
+fn run_amplifier(opcodes: &[Intcode], input: Intcode, phase: &[Intcode]) -> Intcode {
+    phase.iter().fold(input, |next_input, next_phase| {
+        run_program_n(opcodes, &[*next_phase, next_input])
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn sample_aoc7() {
+        assert_eq!(
+            run_amplifier(
+                &[3, 15, 3, 16, 1002, 16, 10, 16, 1, 16, 15, 15, 4, 15, 99, 0, 0,],
+                0,
+                &[4, 3, 2, 1, 0],
+            ),
+            43210
+        )
+    }
+
+    #[test]
+    fn sample_feedback() {
+        assert_eq!(
+            run_feedback(
+                &[
+                    3, 26, 1001, 26, -4, 26, 3, 27, 1002, 27, 2, 27, 1, 27, 26, 27, 4, 27, 1001,
+                    28, -1, 28, 1005, 28, 6, 99, 0, 0, 5,
+                ],
+                &[9, 8, 7, 6, 5],
+            ),
+            Some(139_629_729)
+        );
+
+        // the first amplifier faults, leaving the rest waiting on it
+        assert_eq!(run_feedback(&[3, 0, 42], &[9, 8, 7, 6, 5]), None);
+    }
+
+    #[test]
+    fn permutations_of() {
+        assert_eq!(permutations(&[1, 2, 3], 3).len(), 6);
+        assert_eq!(
+            permutations(&[1, 2, 3], 2),
+            vec![
+                vec![1, 2],
+                vec![1, 3],
+                vec![2, 1],
+                vec![2, 3],
+                vec![3, 1],
+                vec![3, 2]
+            ]
+        );
+        assert!(permutations(&[1], 2).is_empty());
+    }
+
+    #[test]
+    fn best() {
+        let series = [
+            3, 23, 3, 24, 1002, 24, 10, 24, 1002, 23, -1, 23, 101, 5, 23, 23, 1, 24, 23, 23, 4, 23,
+            99, 0, 0,
+        ];
+        let chain = AmplifierChain::new(&series, 5, Mode::Series);
+        assert_eq!(chain.signal(&[0, 1, 2, 3, 4]), Some(54321));
+        assert_eq!(chain.signal(&[0, 1, 2]), None);
+        assert_eq!(
+            chain.best(&[0, 1, 2, 3, 4]),
+            Some((vec![0, 1, 2, 3, 4], 54321))
+        );
+
+        let feedback = [
+            3, 26, 1001, 26, -4, 26, 3, 27, 1002, 27, 2, 27, 1, 27, 26, 27, 4, 27, 1001, 28, -1,
+            28, 1005, 28, 6, 99, 0, 0, 5,
+        ];
+        let chain = AmplifierChain::new(&feedback, 5, Mode::Feedback);
+        assert_eq!(
+            chain.best(&[5, 6, 7, 8, 9]),
+            Some((vec![9, 8, 7, 6, 5], 139_629_729))
+        );
+        assert_eq!(
+            AmplifierChain::new(&feedback, 6, Mode::Feedback).best(&[5, 6]),
+            None
+        );
+    }
+}
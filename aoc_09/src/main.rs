@@ -1,5 +1,3 @@
-mod intcode;
-
 use intcode::{read_input, run_program};
 use std::fs::File;
 
@@ -1,6 +1,6 @@
-mod intcode;
-
-use intcode::{read_input, Computer, ComputerStatus, Intcode};
+use intcode::io::Grouping;
+use intcode::{read_input, Computer, Intcode};
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::fs::File;
 
@@ -94,36 +94,22 @@ impl Robot {
 
     fn run_robot(&mut self, v: &[Intcode]) {
         let mut c = Computer::new(v);
+        let robot = RefCell::new(self);
+        let mut outputs = c.outputs(|| Some(robot.borrow().get()));
 
-        'main: loop {
-            let color = loop {
-                match c.run() {
-                    ComputerStatus::Halt => break 'main,
-                    ComputerStatus::WaitingForInput => c.input_add(self.get()),
-                    ComputerStatus::ReturnedValue => {
-                        break c.output_get();
-                    }
-                }
-            };
-
-            let turn = loop {
-                match c.run() {
-                    ComputerStatus::Halt => break 'main,
-                    ComputerStatus::WaitingForInput => c.input_add(self.get()),
-                    ComputerStatus::ReturnedValue => {
-                        break c.output_get();
-                    }
-                }
-            };
-
-            self.set(color);
+        for [color, turn] in outputs.by_ref().grouped() {
+            let mut robot = robot.borrow_mut();
+            robot.set(color);
             if turn == 0 {
-                self.turn_left();
+                robot.turn_left();
             } else {
-                self.turn_right();
+                robot.turn_right();
             }
-            self.forward();
+            robot.forward();
         }
+        outputs
+            .finish()
+            .unwrap_or_else(|e| panic!("robot crashed: {}", e));
     }
 }
 
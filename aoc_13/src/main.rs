@@ -1,6 +1,7 @@
-mod intcode;
-
-use intcode::{read_input, Computer, ComputerStatus, Intcode};
+use intcode::io::Grouping;
+use intcode::trace::RingTracer;
+use intcode::{read_input, Computer, Intcode};
+use std::cell::{Cell, RefCell};
 use std::cmp::Ordering;
 use std::fmt;
 use std::fs::File;
@@ -62,51 +63,45 @@ fn main() {
     let max_x = v[49] + 1;
     v[0] = 2;
 
-    let mut screen = Screen::new(max_x as usize, max_y as usize);
+    let screen = RefCell::new(Screen::new(max_x as usize, max_y as usize));
     let mut c = Computer::new(&v);
     let mut count = 0;
-    let mut ball_x = 0;
-    let mut cursor_x = 0;
-
-    println!("{}", termion::clear::All);
-    loop {
-        let status = c.run();
-        match status {
-            ComputerStatus::ReturnedValue => {
-                while c.output.len() > 2 {
-                    let x = c.output_get();
-                    let y = c.output_get();
-                    let kind = c.output_get();
+    let ball_x = Cell::new(0);
+    let cursor_x = Cell::new(0);
 
-                    if x < 0 {
-                        screen.score = kind;
-                    } else {
-                        *screen.get_mut(y as usize, x as usize) = kind;
-                    }
+    // the last instructions run, to show how a crash came about
+    let mut trace = RingTracer::new(32);
+    let joystick = || {
+        // std::thread::sleep(std::time::Duration::from_millis(5));
+        println!("{}", screen.borrow());
+        Some(match ball_x.get().cmp(&cursor_x.get()) {
+            Ordering::Equal => 0,
+            Ordering::Less => -1,
+            Ordering::Greater => 1,
+        })
+    };
 
-                    match kind {
-                        2 => count += 1,
-                        4 => ball_x = x,
-                        3 => cursor_x = x,
-                        _ => (),
-                    }
-                }
-            }
-            ComputerStatus::WaitingForInput => {
-                // std::thread::sleep(std::time::Duration::from_millis(5));
-                println!("{}", screen);
-                let mv = match ball_x.cmp(&cursor_x) {
-                    Ordering::Equal => 0,
-                    Ordering::Less => -1,
-                    Ordering::Greater => 1,
-                };
+    println!("{}", termion::clear::All);
+    let mut outputs = c.outputs_traced(joystick, &mut trace);
+    for [x, y, kind] in outputs.by_ref().grouped() {
+        if x < 0 {
+            screen.borrow_mut().score = kind;
+        } else {
+            *screen.borrow_mut().get_mut(y as usize, x as usize) = kind;
+        }
 
-                c.input_add(mv);
-            }
-            ComputerStatus::Halt => break,
+        match kind {
+            2 => count += 1,
+            4 => ball_x.set(x),
+            3 => cursor_x.set(x),
+            _ => (),
         }
     }
+    outputs.finish().unwrap_or_else(|e| {
+        eprint!("{}", trace);
+        panic!("game crashed: {}", e)
+    });
 
-    println!("{}", screen);
+    println!("{}", screen.borrow());
     println!("There are {} blocks", count);
 }
@@ -0,0 +1,75 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use intcode::blocks::BlockComputer;
+use intcode::{read_input, run_program, Instruction, Intcode};
+use std::fs::File;
+
+fn load(day: &str) -> Vec<Intcode> {
+    let mut file = File::open(
+        std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("..")
+            .join(day)
+            .join("input.txt"),
+    )
+    .expect("unable to open input.txt");
+    read_input(&mut file).expect("parse error")
+}
+
+// The decoding `Computer::run` used to do on every step
+fn decode_string(word: Intcode) -> (u8, u8, u8, Intcode) {
+    let full_instruction = format!("{:05}", word).bytes().collect::<Vec<u8>>();
+    let instruction = std::str::from_utf8(&full_instruction[3..])
+        .expect("invalid utf8")
+        .parse()
+        .expect("not a number");
+
+    (
+        full_instruction[0],
+        full_instruction[1],
+        full_instruction[2],
+        instruction,
+    )
+}
+
+fn decode(c: &mut Criterion) {
+    let words: Vec<_> = load("aoc_13")
+        .into_iter()
+        .filter(|&w| Instruction::decode(0, w).is_ok())
+        .collect();
+
+    let mut group = c.benchmark_group("decode");
+    group.bench_function("string", |b| {
+        b.iter(|| {
+            for &w in &words {
+                black_box(decode_string(black_box(w)));
+            }
+        })
+    });
+    group.bench_function("arithmetic", |b| {
+        b.iter(|| {
+            for &w in &words {
+                black_box(Instruction::decode(0, black_box(w)).unwrap());
+            }
+        })
+    });
+    group.finish();
+}
+
+fn run(c: &mut Criterion) {
+    let v = load("aoc_09");
+
+    c.bench_function("aoc_09 part 2", |b| {
+        b.iter(|| run_program(black_box(&v), 2))
+    });
+    // the one configuration both engines share: `Intcode` words on `Vec` memory, untraced
+    c.bench_function("aoc_09 part 2 blocks", |b| {
+        b.iter(|| {
+            let mut c = BlockComputer::new(black_box(&v));
+            c.input_add(2);
+            c.run();
+            c.output_get()
+        })
+    });
+}
+
+criterion_group!(benches, decode, run);
+criterion_main!(benches);
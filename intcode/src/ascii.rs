@@ -0,0 +1,139 @@
+use crate::{Computer, ComputerStatus, Intcode, IntcodeError, Memory};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Output {
+    Text(String),
+    /// A value outside the ASCII range, such as a puzzle answer
+    Number(Intcode),
+}
+
+impl fmt::Display for Output {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Output::Text(text) => write!(f, "{}", text),
+            Output::Number(n) => writeln!(f, "{}", n),
+        }
+    }
+}
+
+/// Group consecutive ASCII values into text, leaving anything else as a number
+pub fn decode<I: IntoIterator<Item = Intcode>>(values: I) -> Vec<Output> {
+    let mut outputs = Vec::new();
+
+    for value in values {
+        match (value, outputs.last_mut()) {
+            (0..=127, Some(Output::Text(text))) => text.push(value as u8 as char),
+            (0..=127, _) => outputs.push(Output::Text((value as u8 as char).to_string())),
+            _ => outputs.push(Output::Number(value)),
+        }
+    }
+
+    outputs
+}
+
+/// The input values for `line` followed by a newline
+pub fn encode_line(line: &str) -> impl Iterator<Item = Intcode> + '_ {
+    line.bytes().chain(Some(b'\n')).map(Intcode::from)
+}
+
+/// Talks to a program that reads and prints lines of text
+pub struct Console<M = Vec<Intcode>> {
+    pub computer: Computer<Intcode, M>,
+}
+
+impl Console {
+    pub fn new(program: &[Intcode]) -> Self {
+        Self {
+            computer: Computer::new(program),
+        }
+    }
+}
+
+impl<M: Memory> Console<M> {
+    pub fn send_line(&mut self, line: &str) {
+        for value in encode_line(line) {
+            self.computer.input_add(value);
+        }
+    }
+
+    /// Run until the program halts or wants another line, returning what it printed, even
+    /// when it then crashed
+    pub fn run(&mut self) -> (Vec<Output>, Result<ComputerStatus, IntcodeError>) {
+        let status = loop {
+            match self.computer.try_run() {
+                Ok(ComputerStatus::ReturnedValue) => (),
+                status => break status,
+            }
+        };
+
+        (decode(self.computer.output.drain(..)), status)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::asm::assemble;
+
+    #[test]
+    fn decoding() {
+        assert_eq!(
+            decode(vec![72, 105, 10, 1234, -1, 33]),
+            vec![
+                Output::Text("Hi\n".into()),
+                Output::Number(1234),
+                Output::Number(-1),
+                Output::Text("!".into()),
+            ]
+        );
+        assert_eq!(encode_line("ok").collect::<Vec<_>>(), vec![111, 107, 10]);
+    }
+
+    #[test]
+    fn console() {
+        // greets, then reports 1000 plus the length of the line it reads
+        let program = assemble(
+            "
+                    out  #104
+                    out  #105
+                    out  #10
+            loop:   in   [c]
+                    eq   [c], #10, [t]
+                    jt   [t], #done
+                    add  [n], #1, [n]
+                    jt   #1, #loop
+            done:   out  [n]
+                    out  #63
+                    hlt
+            c:      data 0
+            t:      data 0
+            n:      data 1000
+            ",
+        )
+        .unwrap();
+
+        let mut console = Console::new(&program);
+        assert_eq!(
+            console.run(),
+            (
+                vec![Output::Text("hi\n".into())],
+                Ok(ComputerStatus::WaitingForInput)
+            )
+        );
+
+        console.send_line("abc");
+        let (outputs, status) = console.run();
+        assert_eq!(status, Ok(ComputerStatus::Halt));
+        assert_eq!(
+            outputs.iter().map(|o| o.to_string()).collect::<String>(),
+            "1003\n?"
+        );
+
+        // what was printed before a crash is kept
+        let mut console = Console::new(&assemble("out #33\nout #10\ndata 42").unwrap());
+        let (outputs, status) = console.run();
+        assert_eq!(outputs, vec![Output::Text("!\n".into())]);
+        assert!(status.is_err());
+    }
+}
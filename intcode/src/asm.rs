@@ -0,0 +1,300 @@
+use crate::disasm::{Operand, Value};
+use crate::{Instruction, Intcode, Opcode, ParamMode};
+use std::collections::HashMap;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AsmError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for AsmError {}
+
+enum Statement {
+    Instruction(Opcode, Vec<Operand>),
+    Data(Vec<Value>),
+}
+
+impl Statement {
+    fn size(&self) -> usize {
+        match self {
+            Statement::Instruction(opcode, _) => opcode.params() + 1,
+            Statement::Data(values) => values.len(),
+        }
+    }
+}
+
+fn is_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn parse_value(s: &str) -> Result<Value, String> {
+    let s = s.trim();
+    if let Ok(n) = s.parse() {
+        Ok(Value::Number(n))
+    } else if is_identifier(s) {
+        Ok(Value::Label(s.to_string()))
+    } else {
+        Err(format!("invalid value: {:?}", s))
+    }
+}
+
+fn parse_operand(s: &str) -> Result<Operand, String> {
+    let s = s.trim();
+    let (mode, value) = if let Some(inner) = s.strip_prefix('[') {
+        let inner = inner
+            .strip_suffix(']')
+            .ok_or_else(|| format!("unclosed bracket: {:?}", s))?;
+        (ParamMode::Position, parse_value(inner)?)
+    } else if let Some(imm) = s.strip_prefix('#') {
+        (ParamMode::Immediate, parse_value(imm)?)
+    } else if s.get(..2).is_some_and(|p| p.eq_ignore_ascii_case("rb")) {
+        let offset = s[2..].trim();
+        let value = if offset.is_empty() {
+            Value::Number(0)
+        } else if let Some(offset) = offset.strip_prefix('+') {
+            parse_value(offset)?
+        } else if let Ok(n) = offset.parse::<Intcode>() {
+            Value::Number(n)
+        } else {
+            return Err(format!("invalid relative operand: {:?}", s));
+        };
+        (ParamMode::Relative, value)
+    } else {
+        return Err(format!("operand needs a mode ([x], #x or rb+x): {:?}", s));
+    };
+
+    Ok(Operand { mode, value })
+}
+
+fn parse_statement(mnemonic: &str, args: &str) -> Result<Statement, String> {
+    let args: Vec<_> = if args.trim().is_empty() {
+        Vec::new()
+    } else {
+        args.split(',').collect()
+    };
+
+    if mnemonic.eq_ignore_ascii_case("DATA") {
+        return Ok(Statement::Data(
+            args.iter()
+                .map(|a| parse_value(a))
+                .collect::<Result<_, _>>()?,
+        ));
+    }
+
+    let opcode = Opcode::from_mnemonic(mnemonic)
+        .ok_or_else(|| format!("unknown mnemonic: {:?}", mnemonic))?;
+    if args.len() != opcode.params() {
+        return Err(format!(
+            "{} takes {} operands, found {}",
+            opcode.mnemonic(),
+            opcode.params(),
+            args.len()
+        ));
+    }
+
+    let operands: Vec<_> = args
+        .iter()
+        .map(|a| parse_operand(a))
+        .collect::<Result<_, _>>()?;
+    if opcode.writes() && operands.last().map(|o| o.mode) == Some(ParamMode::Immediate) {
+        return Err(format!(
+            "{} cannot write to an immediate",
+            opcode.mnemonic()
+        ));
+    }
+
+    Ok(Statement::Instruction(opcode, operands))
+}
+
+/// Parse a single source line into an optional label and statement. A leading address
+/// column, as printed by the disassembler, is ignored.
+fn parse_line(line: &str) -> Result<(Option<&str>, Option<Statement>), String> {
+    let mut rest = line.split(';').next().unwrap_or("").trim();
+
+    if let Some((first, tail)) = rest.split_once(char::is_whitespace) {
+        if first.parse::<usize>().is_ok() {
+            rest = tail.trim();
+        }
+    }
+
+    let mut label = None;
+    if let Some((name, tail)) = rest.split_once(':') {
+        let name = name.trim();
+        if !is_identifier(name) {
+            return Err(format!("invalid label: {:?}", name));
+        }
+        label = Some(name);
+        rest = tail.trim();
+    }
+
+    if rest.is_empty() {
+        return Ok((label, None));
+    }
+
+    let (mnemonic, args) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+    Ok((label, Some(parse_statement(mnemonic, args)?)))
+}
+
+pub fn assemble(source: &str) -> Result<Vec<Intcode>, AsmError> {
+    let mut labels = HashMap::new();
+    let mut statements = Vec::new();
+    let mut address = 0;
+
+    for (idx, line) in source.lines().enumerate() {
+        let error = |message| AsmError {
+            line: idx + 1,
+            message,
+        };
+
+        let (label, statement) = parse_line(line).map_err(error)?;
+        if let Some(label) = label {
+            if labels.insert(label.to_string(), address).is_some() {
+                return Err(error(format!("duplicate label: {:?}", label)));
+            }
+        }
+
+        if let Some(statement) = statement {
+            address += statement.size();
+            statements.push((idx + 1, statement));
+        }
+    }
+
+    let mut program = Vec::with_capacity(address);
+    for (line, statement) in statements {
+        let resolve = |value: &Value| match value {
+            Value::Number(n) => Ok(*n),
+            Value::Label(l) => labels.get(l).map(|&a| a as Intcode).ok_or(AsmError {
+                line,
+                message: format!("unknown label: {:?}", l),
+            }),
+        };
+
+        match statement {
+            Statement::Instruction(opcode, operands) => {
+                let mut modes = [ParamMode::Position; 3];
+                modes
+                    .iter_mut()
+                    .zip(operands.iter())
+                    .for_each(|(m, o)| *m = o.mode);
+
+                program.push(Instruction { opcode, modes }.encode());
+                for operand in &operands {
+                    program.push(resolve(&operand.value)?);
+                }
+            }
+            Statement::Data(values) => {
+                for value in &values {
+                    program.push(resolve(value)?);
+                }
+            }
+        }
+    }
+
+    Ok(program)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::disasm::disassemble;
+    use crate::run_program;
+
+    const SAMPLE_4: [Intcode; 47] = [
+        3, 21, 1008, 21, 8, 20, 1005, 20, 22, 107, 8, 21, 20, 1006, 20, 31, 1106, 0, 36, 98, 0, 0,
+        1002, 21, 125, 20, 4, 20, 1105, 1, 46, 104, 999, 1105, 1, 46, 1101, 1000, 1, 20, 4, 20,
+        1105, 1, 46, 98, 99,
+    ];
+
+    #[test]
+    fn assemble_program() {
+        let source = "
+            ; echo the input back plus one
+                    arb  #1
+            start:  in   rb+0
+                    add  rb+0, #1, [value]
+                    out  [value]
+                    jt   #1, #start
+            value:  data 0
+        ";
+
+        assert_eq!(
+            assemble(source),
+            Ok(vec![109, 1, 203, 0, 1201, 0, 1, 13, 4, 13, 1105, 1, 2, 0])
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let listing = disassemble(&SAMPLE_4).to_string();
+        assert_eq!(assemble(&listing), Ok(SAMPLE_4.to_vec()));
+
+        let odd = [1099, 104, 1, 99];
+        assert_eq!(assemble(&disassemble(&odd).to_string()), Ok(odd.to_vec()));
+    }
+
+    #[test]
+    fn readable_sample_4() {
+        let program = assemble(
+            "
+                    in   [n]
+                    lt   [n], #8, [tmp]
+                    jt   [tmp], #below
+                    eq   [n], #8, [tmp]
+                    jt   [tmp], #equal
+                    out  #1001
+                    hlt
+            below:  out  #999
+                    hlt
+            equal:  out  #1000
+                    hlt
+            n:      data 0
+            tmp:    data 0
+            ",
+        )
+        .unwrap();
+
+        for case in [(9, 1001), (8, 1000), (7, 999)].iter() {
+            assert_eq!(run_program(&program, case.0), case.1);
+            assert_eq!(run_program(&SAMPLE_4, case.0), case.1);
+        }
+    }
+
+    #[test]
+    fn errors() {
+        for case in [
+            ("  foo #1", 1, "unknown mnemonic: \"foo\""),
+            ("add #1, #2", 1, "ADD takes 3 operands, found 2"),
+            ("add #1, #2, #3", 1, "ADD cannot write to an immediate"),
+            ("out 5", 1, "operand needs a mode ([x], #x or rb+x): \"5\""),
+            ("a: hlt\na: hlt", 2, "duplicate label: \"a\""),
+            ("hlt\njt #1, #nowhere", 2, "unknown label: \"nowhere\""),
+            ("out rb-x", 1, "invalid relative operand: \"rb-x\""),
+            (
+                "out aé",
+                1,
+                "operand needs a mode ([x], #x or rb+x): \"aé\"",
+            ),
+            ("out é", 1, "operand needs a mode ([x], #x or rb+x): \"é\""),
+        ]
+        .iter()
+        {
+            assert_eq!(
+                assemble(case.0),
+                Err(AsmError {
+                    line: case.1,
+                    message: case.2.to_string(),
+                })
+            );
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use crate::{Computer, ComputerStatus, IntcodeError, Memory, Word};
+use futures::{Sink, SinkExt, Stream, StreamExt};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RunError<E> {
+    Fault(IntcodeError),
+    /// The program asked for input after the input stream ended
+    InputClosed,
+    Output(E),
+}
+
+impl<E: fmt::Display> fmt::Display for RunError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RunError::Fault(e) => write!(f, "{}", e),
+            RunError::InputClosed => write!(f, "input closed while waiting for input"),
+            RunError::Output(e) => write!(f, "unable to send output: {}", e),
+        }
+    }
+}
+
+impl<E: fmt::Debug + fmt::Display> std::error::Error for RunError<E> {}
+
+impl<W: Word, M: Memory<W>> Computer<W, M> {
+    /// Run to completion, awaiting `input` whenever the program executes `IN` and sending
+    /// every output into `output`. Any number of these can be driven together on a single
+    /// threaded executor, with channels connecting them into a network.
+    pub async fn run_async<S, K>(
+        &mut self,
+        mut input: S,
+        mut output: K,
+    ) -> Result<(), RunError<K::Error>>
+    where
+        S: Stream<Item = W> + Unpin,
+        K: Sink<W> + Unpin,
+    {
+        loop {
+            match self.try_run().map_err(RunError::Fault)? {
+                ComputerStatus::ReturnedValue | ComputerStatus::BudgetExhausted => {
+                    while let Some(value) = self.output.pop_front() {
+                        output.send(value).await.map_err(RunError::Output)?;
+                    }
+                }
+                ComputerStatus::WaitingForInput => match input.next().await {
+                    Some(value) => self.input_add(value),
+                    None => return Err(RunError::InputClosed),
+                },
+                ComputerStatus::Halt => return Ok(()),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Intcode;
+    use futures::channel::mpsc;
+    use futures::executor::block_on;
+    use futures::future::join_all;
+
+    // day 7 feedback loop sample, best phases 9,8,7,6,5
+    const FEEDBACK: [Intcode; 29] = [
+        3, 26, 1001, 26, -4, 26, 3, 27, 1002, 27, 2, 27, 1, 27, 26, 27, 4, 27, 1001, 28, -1, 28,
+        1005, 28, 6, 99, 0, 0, 5,
+    ];
+
+    #[test]
+    fn ring() {
+        let phases = [9, 8, 7, 6, 5];
+        let (senders, mut receivers): (Vec<_>, Vec<_>) = phases
+            .iter()
+            .map(|&phase| {
+                let (tx, rx) = mpsc::unbounded();
+                tx.unbounded_send(phase).unwrap();
+                (tx, rx)
+            })
+            .unzip();
+        senders[0].unbounded_send(0).unwrap();
+
+        let mut amps = vec![Computer::new(&FEEDBACK); phases.len()];
+        let runs = amps
+            .iter_mut()
+            .zip(receivers.iter_mut())
+            .enumerate()
+            .map(|(idx, (amp, rx))| amp.run_async(rx, senders[(idx + 1) % phases.len()].clone()));
+        let results = block_on(join_all(runs));
+
+        assert!(results.iter().all(|r| r.is_ok()));
+        assert_eq!(receivers[0].try_recv().unwrap(), 139_629_729);
+    }
+
+    #[test]
+    fn errors() {
+        let (tx, rx) = mpsc::unbounded::<Intcode>();
+        drop(tx);
+        let (out, _) = mpsc::unbounded();
+        let mut c = Computer::new(&[3, 0, 99]);
+        assert_eq!(block_on(c.run_async(rx, out)), Err(RunError::InputClosed));
+
+        let (out, out_rx) = mpsc::unbounded();
+        drop(out_rx);
+        let mut c = Computer::new(&[104, 1, 99]);
+        let result = block_on(c.run_async(futures::stream::empty(), out));
+        assert!(matches!(result, Err(RunError::Output(e)) if e.is_disconnected()));
+
+        let mut c = Computer::new(&[42]);
+        assert_eq!(
+            block_on(c.run_async(futures::stream::empty(), futures::sink::drain())),
+            Err(RunError::Fault(IntcodeError::UnknownOpcode {
+                pc: 0,
+                opcode: 42
+            }))
+        );
+    }
+}
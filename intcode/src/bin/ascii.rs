@@ -0,0 +1,31 @@
+use intcode::ascii::Console;
+use intcode::{read_input, ComputerStatus};
+use std::fs::File;
+use std::io::{self, BufRead, Write};
+
+fn main() {
+    let path = std::env::args().nth(1).expect("usage: ascii PROGRAM");
+    let program =
+        read_input(&mut File::open(path).expect("unable to open program")).expect("parse error");
+
+    let mut console = Console::new(&program);
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    loop {
+        let (outputs, status) = console.run();
+        outputs.iter().for_each(|o| print!("{}", o));
+        io::stdout().flush().expect("unable to flush stdout");
+        let status = status.unwrap_or_else(|e| {
+            eprintln!("error: {}", e);
+            std::process::exit(1)
+        });
+
+        if status == ComputerStatus::Halt {
+            break;
+        }
+        match lines.next() {
+            Some(line) => console.send_line(&line.expect("unable to read")),
+            None => break,
+        }
+    }
+}
@@ -0,0 +1,13 @@
+use intcode::{cfg::Cfg, read_input};
+use std::fs::File;
+use std::io;
+
+fn main() {
+    let program = match std::env::args().nth(1) {
+        Some(path) => read_input(&mut File::open(path).expect("unable to open program")),
+        None => read_input(&mut io::stdin()),
+    }
+    .expect("parse error");
+
+    print!("{}", Cfg::build(&program).dot());
+}
@@ -0,0 +1,223 @@
+use intcode::debugger::Debugger;
+use intcode::disasm::disassemble_at;
+use intcode::{read_input, Intcode, Snapshot};
+use std::fs::{self, File};
+use std::io::{self, BufRead, Write};
+
+/// Most words a single `mem` command prints
+const MEM_LIMIT: usize = 4096;
+
+const HELP: &str = "\
+s, step [N]          execute N instructions (default 1)
+c, continue          run until a breakpoint, watchpoint, input request, halt or error
+bs, back [N]         undo N instructions (default 1)
+seek N               go back or forward to when N instructions had run
+lw, lastwrite ADDR   go back to the last instruction that wrote to ADDR
+b, break [ADDR]      set a breakpoint on PC, or list breakpoints
+d, delete ADDR       remove a breakpoint
+w, watch [ADDR]      stop when memory at ADDR changes, or list watchpoints
+u, unwatch ADDR      remove a watchpoint
+i, input N...        queue input values
+r, regs              show program counter, base offset and I/O queues
+m, mem START [LEN]   dump memory, at most 4096 words
+l, list [N]          disassemble N instructions from the program counter (default 5)
+save FILE            dump the machine state, as JSON if FILE ends in .json
+load FILE            restore a state written by save
+h, help              show this message
+q, quit              exit
+an empty line repeats the previous command";
+
+fn parse_args<T: std::str::FromStr>(args: &[&str]) -> Result<Vec<T>, String> {
+    args.iter()
+        .map(|a| a.parse().map_err(|_| format!("invalid number: {}", a)))
+        .collect()
+}
+
+fn print_location(d: &Debugger) {
+    let pc = d.computer.program_counter();
+    println!("{:>5}  {}", pc, disassemble_at(d.computer.memory(), pc));
+}
+
+fn command(d: &mut Debugger, line: &str) -> Result<bool, String> {
+    let words: Vec<_> = line.split_whitespace().collect();
+    let (cmd, args) = match words.split_first() {
+        Some((cmd, args)) => (*cmd, args),
+        None => return Ok(true),
+    };
+
+    match cmd {
+        "s" | "step" => {
+            let count = parse_args::<usize>(args)?.first().copied().unwrap_or(1);
+            for _ in 0..count {
+                if let Some(stop) = d.step() {
+                    println!("{}", stop);
+                    break;
+                }
+            }
+            print_location(d);
+        }
+        "c" | "continue" => {
+            println!("{}", d.cont());
+            print_location(d);
+        }
+        "bs" | "back" => {
+            let count = parse_args::<usize>(args)?.first().copied().unwrap_or(1);
+            for _ in 0..count {
+                if !d.step_back() {
+                    println!("at the start of the history");
+                    break;
+                }
+            }
+            print_location(d);
+        }
+        "seek" => {
+            let count = *parse_args::<usize>(args)?.first().ok_or("missing count")?;
+            if let Some(status) = d.seek(count).map_err(|e| e.to_string())? {
+                println!("{:?}", status);
+            }
+            print_location(d);
+        }
+        "lw" | "lastwrite" => {
+            let address = *parse_args::<usize>(args)?
+                .first()
+                .ok_or("missing address")?;
+            let history = d.computer.history().ok_or("history is not recorded")?;
+            let count = history
+                .last_write(address)
+                .ok_or_else(|| format!("[{}] not written", address))?;
+            d.seek(count).map_err(|e| e.to_string())?;
+            print_location(d);
+        }
+        "b" | "break" => match parse_args::<usize>(args)?.first() {
+            Some(&pc) => {
+                d.add_breakpoint(pc);
+            }
+            None => d.breakpoints().for_each(|pc| println!("{}", pc)),
+        },
+        "d" | "delete" => {
+            for pc in parse_args::<usize>(args)? {
+                if !d.remove_breakpoint(pc) {
+                    println!("no breakpoint at {}", pc);
+                }
+            }
+        }
+        "w" | "watch" => match parse_args::<usize>(args)?.first() {
+            Some(&address) => {
+                d.watch(address);
+            }
+            None => d
+                .watchpoints()
+                .for_each(|&a| println!("[{}] = {}", a, d.peek(a))),
+        },
+        "u" | "unwatch" => {
+            for address in parse_args::<usize>(args)? {
+                if !d.unwatch(address) {
+                    println!("no watchpoint on {}", address);
+                }
+            }
+        }
+        "i" | "input" => {
+            let values = parse_args::<Intcode>(args)?;
+            d.computer.input_add_all(values.iter());
+        }
+        "r" | "regs" => {
+            println!("PC: {}", d.computer.program_counter());
+            if let Some(history) = d.computer.history() {
+                println!("STEPS: {}", history.len());
+            }
+            println!("BO: {}", d.computer.base_offset());
+            println!("IN: {:?}", d.computer.input());
+            println!("OUT: {:?}", d.computer.output);
+        }
+        "m" | "mem" => {
+            let args = parse_args::<usize>(args)?;
+            let start = *args.first().ok_or("missing start address")?;
+            let len = args.get(1).copied().unwrap_or(1).min(MEM_LIMIT);
+            let end = start.checked_add(len).ok_or("address out of range")?;
+
+            for row in (start..end).step_by(8) {
+                let values: Vec<_> = (row..end.min(row.saturating_add(8)))
+                    .map(|a| d.peek(a).to_string())
+                    .collect();
+                println!("{:>5}  {}", row, values.join(", "));
+            }
+        }
+        "l" | "list" => {
+            let count = parse_args::<usize>(args)?.first().copied().unwrap_or(5);
+            let mut pc = d.computer.program_counter();
+            for _ in 0..count {
+                let line = disassemble_at(d.computer.memory(), pc);
+                println!("{:>5}  {}", pc, line);
+                pc += line.size();
+            }
+        }
+        "save" => {
+            let path = args.first().ok_or("missing file name")?;
+            let snapshot = d.computer.snapshot();
+            let data = if path.ends_with(".json") {
+                snapshot.to_json().into_bytes()
+            } else {
+                snapshot.to_bytes()
+            };
+            fs::write(path, data).map_err(|e| format!("{}: {}", path, e))?;
+        }
+        "load" => {
+            let path = args.first().ok_or("missing file name")?;
+            let data = fs::read(path).map_err(|e| format!("{}: {}", path, e))?;
+            let snapshot = if path.ends_with(".json") {
+                let json = String::from_utf8(data).map_err(|e| e.to_string())?;
+                Snapshot::from_json(&json).map_err(|e| e.to_string())?
+            } else {
+                Snapshot::from_bytes(&data).map_err(|e| e.to_string())?
+            };
+            d.restore(snapshot).map_err(|e| e.to_string())?;
+            print_location(d);
+        }
+        "h" | "help" => println!("{}", HELP),
+        "q" | "quit" => return Ok(false),
+        x => return Err(format!("unknown command: {} (try help)", x)),
+    }
+
+    Ok(true)
+}
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let path = args.next().expect("usage: debugger PROGRAM [INPUT...]");
+    let program =
+        read_input(&mut File::open(path).expect("unable to open program")).expect("parse error");
+
+    let mut d = Debugger::new(&program);
+    d.computer.record_history();
+    let input: Vec<Intcode> = args.map(|a| a.parse().expect("invalid input")).collect();
+    d.computer.input_add_all(input.iter());
+
+    print_location(&d);
+    let mut previous = String::new();
+    let mut seen = 0;
+    let stdin = io::stdin();
+    loop {
+        print!("(icdb) ");
+        io::stdout().flush().expect("unable to flush stdout");
+
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line).expect("unable to read") == 0 {
+            break;
+        }
+        if line.trim().is_empty() {
+            line = previous.clone();
+        }
+
+        match command(&mut d, &line) {
+            Ok(true) => (),
+            Ok(false) => break,
+            Err(e) => println!("{}", e),
+        }
+        previous = line;
+
+        d.computer.output.iter().skip(seen).for_each(|v| {
+            println!("output: {}", v);
+        });
+        seen = d.computer.output.len();
+    }
+}
@@ -0,0 +1,19 @@
+use intcode::fuzz::{engines, fuzz, Profile};
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let seed = args
+        .next()
+        .map_or(1, |s| s.parse().expect("usage: fuzz [SEED] [CASES]"));
+    let cases = args
+        .next()
+        .map_or(10_000, |s| s.parse().expect("usage: fuzz [SEED] [CASES]"));
+
+    match fuzz(seed, cases, &Profile::full(), &engines()) {
+        Ok(compared) => println!("{} of {} cases compared, no divergence", compared, cases),
+        Err(divergence) => {
+            print!("{}", divergence);
+            std::process::exit(1)
+        }
+    }
+}
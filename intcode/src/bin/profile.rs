@@ -0,0 +1,23 @@
+use intcode::io::from_iter;
+use intcode::{read_input, Computer, Intcode};
+use std::fs::File;
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let path = args.next().expect("usage: profile PROGRAM [INPUT...]");
+    let program =
+        read_input(&mut File::open(path).expect("unable to open program")).expect("parse error");
+    let input: Vec<Intcode> = args.map(|a| a.parse().expect("invalid input")).collect();
+
+    // run until the program halts, faults or wants more input than given
+    let mut c = Computer::new(&program);
+    c.profile();
+    let mut outputs = c.outputs(from_iter(input));
+    let count = outputs.by_ref().count();
+    if let Err(e) = outputs.finish() {
+        eprintln!("error: {}", e);
+    }
+
+    println!("{} outputs", count);
+    print!("{}", c.profiler().expect("profiling").report(10));
+}
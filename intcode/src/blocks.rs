@@ -0,0 +1,532 @@
+use crate::{ComputerStatus, Instruction, Intcode, IntcodeError, Memory, Opcode, ParamMode};
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+#[derive(Debug, Clone, Copy)]
+struct Param {
+    mode: ParamMode,
+    value: Intcode,
+}
+
+/// A pre-decoded instruction
+#[derive(Debug, Clone)]
+enum Op {
+    Add(Param, Param, Param),
+    Mul(Param, Param, Param),
+    Input(Param),
+    Output(Param),
+    JumpIfTrue(Param, Param),
+    JumpIfFalse(Param, Param),
+    LessThan(Param, Param, Param),
+    Equals(Param, Param, Param),
+    AdjustBase(Param),
+    Halt,
+    /// The word does not decode, which only matters once it is reached
+    Fault(IntcodeError),
+}
+
+#[derive(Debug, Clone)]
+struct Step {
+    pc: usize,
+    next: usize,
+    op: Op,
+}
+
+/// Straight-line code from `start` up to and including the first jump, halt or fault
+#[derive(Debug)]
+struct Block {
+    start: usize,
+    end: usize,
+    steps: Vec<Step>,
+}
+
+enum Flow {
+    Next,
+    Jump(usize),
+    /// The step wrote into compiled code, so the rest of its block is stale
+    Invalidated,
+    Stop(ComputerStatus),
+}
+
+/// Runs programs like `Computer::new` does, with the same results, but decodes each stretch of
+/// straight-line code only once. Writes into decoded code throw the affected blocks away, so
+/// self-modifying programs still behave.
+///
+/// Only `Intcode` words on plain `Vec` memory are supported, and only the methods below: there
+/// is no tracing, budget, history or snapshot, so it is not a drop-in for `Computer`.
+#[derive(Clone)]
+pub struct BlockComputer {
+    input: VecDeque<Intcode>,
+    pub output: VecDeque<Intcode>,
+    memory: Vec<Intcode>,
+    program_counter: usize,
+    current_instruction: usize,
+    base_offset: isize,
+    checked: bool,
+    pub halted: bool,
+    /// Compiled blocks by start address
+    blocks: Vec<Option<Rc<Block>>>,
+    /// Addresses that are, or were, part of a compiled block
+    code: Vec<bool>,
+    longest: usize,
+}
+
+impl BlockComputer {
+    pub fn new(program: &[Intcode]) -> Self {
+        Self {
+            input: VecDeque::new(),
+            output: VecDeque::new(),
+            memory: program.to_vec(),
+            program_counter: 0,
+            current_instruction: 0,
+            base_offset: 0,
+            checked: false,
+            halted: false,
+            blocks: Vec::new(),
+            code: Vec::new(),
+            longest: 0,
+        }
+    }
+
+    /// In checked mode arithmetic that overflows fails with `IntcodeError::Overflow`
+    pub fn set_checked(&mut self, checked: bool) {
+        self.checked = checked;
+    }
+
+    pub fn input_add(&mut self, input: Intcode) {
+        self.input.push_back(input)
+    }
+
+    pub fn input_add_all<'a, I: IntoIterator<Item = &'a Intcode>>(&mut self, input: I) {
+        self.input.extend(input.into_iter().cloned())
+    }
+
+    pub fn output_get(&mut self) -> Intcode {
+        self.try_output_get().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_output_get(&mut self) -> Result<Intcode, IntcodeError> {
+        self.output.pop_front().ok_or(IntcodeError::OutputUnderflow)
+    }
+
+    pub fn program_counter(&self) -> usize {
+        self.program_counter
+    }
+
+    pub fn base_offset(&self) -> isize {
+        self.base_offset
+    }
+
+    pub fn memory(&self) -> &Vec<Intcode> {
+        &self.memory
+    }
+
+    pub fn input(&self) -> &VecDeque<Intcode> {
+        &self.input
+    }
+
+    pub fn run(&mut self) -> ComputerStatus {
+        self.try_run().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_run(&mut self) -> Result<ComputerStatus, IntcodeError> {
+        loop {
+            if let Some(status) = self.advance(false)? {
+                return Ok(status);
+            }
+        }
+    }
+
+    /// Execute a single instruction, returning the status `run` would stop with, if any
+    pub fn step(&mut self) -> Result<Option<ComputerStatus>, IntcodeError> {
+        self.advance(true)
+    }
+
+    /// Run the block at the program counter, or only its first step if `single`
+    fn advance(&mut self, single: bool) -> Result<Option<ComputerStatus>, IntcodeError> {
+        let block = self.block(self.program_counter);
+
+        for step in &block.steps {
+            self.current_instruction = step.pc;
+            match self.execute(step) {
+                Ok(Flow::Next) => self.program_counter = step.next,
+                Ok(Flow::Jump(to)) => {
+                    self.program_counter = to;
+                    return Ok(None);
+                }
+                Ok(Flow::Invalidated) => {
+                    self.program_counter = step.next;
+                    return Ok(None);
+                }
+                Ok(Flow::Stop(status)) => return Ok(Some(status)),
+                Err(e) => {
+                    // leave the program counter on the faulting instruction
+                    self.program_counter = step.pc;
+                    return Err(e);
+                }
+            }
+
+            if single {
+                break;
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn block(&mut self, start: usize) -> Rc<Block> {
+        if let Some(Some(block)) = self.blocks.get(start) {
+            return block.clone();
+        }
+
+        let block = Rc::new(self.compile(start));
+        if start >= self.memory.len() {
+            // only zeros out there, not worth growing the tables for
+            return block;
+        }
+        if self.blocks.len() <= start {
+            self.blocks.resize(start + 1, None);
+        }
+        if self.code.len() < block.end {
+            self.code.resize(block.end, false);
+        }
+        for covered in &mut self.code[block.start..block.end] {
+            *covered = true;
+        }
+        self.longest = self.longest.max(block.end - block.start);
+        self.blocks[start] = Some(block.clone());
+        block
+    }
+
+    fn compile(&self, start: usize) -> Block {
+        let mut steps = Vec::new();
+        let mut pc = start;
+
+        loop {
+            let param = |idx: usize, mode: ParamMode| Param {
+                mode,
+                value: self.memory.read(pc + 1 + idx),
+            };
+
+            let (op, size) = match Instruction::decode(pc, self.memory.read(pc)) {
+                Ok(instruction) => {
+                    let [a, b, c] = instruction.modes;
+                    let op = match instruction.opcode {
+                        Opcode::Add => Op::Add(param(0, a), param(1, b), param(2, c)),
+                        Opcode::Mul => Op::Mul(param(0, a), param(1, b), param(2, c)),
+                        Opcode::Input => Op::Input(param(0, a)),
+                        Opcode::Output => Op::Output(param(0, a)),
+                        Opcode::JumpIfTrue => Op::JumpIfTrue(param(0, a), param(1, b)),
+                        Opcode::JumpIfFalse => Op::JumpIfFalse(param(0, a), param(1, b)),
+                        Opcode::LessThan => Op::LessThan(param(0, a), param(1, b), param(2, c)),
+                        Opcode::Equals => Op::Equals(param(0, a), param(1, b), param(2, c)),
+                        Opcode::AdjustBase => Op::AdjustBase(param(0, a)),
+                        Opcode::Halt => Op::Halt,
+                    };
+                    (op, instruction.size())
+                }
+                Err(e) => (Op::Fault(e), 1),
+            };
+
+            let last = matches!(
+                op,
+                Op::JumpIfTrue(..) | Op::JumpIfFalse(..) | Op::Halt | Op::Fault(_)
+            );
+            steps.push(Step {
+                pc,
+                next: pc + size,
+                op,
+            });
+            pc += size;
+
+            if last {
+                return Block {
+                    start,
+                    end: pc,
+                    steps,
+                };
+            }
+        }
+    }
+
+    /// Drop every block holding `address`, returning whether there were any
+    fn invalidate(&mut self, address: usize) -> bool {
+        if !self.code.get(address).copied().unwrap_or(false) {
+            return false;
+        }
+
+        let first = (address + 1).saturating_sub(self.longest);
+        let last = address.min(self.blocks.len().saturating_sub(1));
+        let mut invalidated = false;
+        for start in first..=last {
+            if let Some(block) = &self.blocks[start] {
+                if address < block.end {
+                    self.blocks[start] = None;
+                    invalidated = true;
+                }
+            }
+        }
+        invalidated
+    }
+
+    fn overflow(&self) -> IntcodeError {
+        IntcodeError::Overflow {
+            pc: self.current_instruction,
+        }
+    }
+
+    fn add(&self, a: Intcode, b: Intcode) -> Result<Intcode, IntcodeError> {
+        if self.checked {
+            a.checked_add(b).ok_or_else(|| self.overflow())
+        } else {
+            Ok(a + b)
+        }
+    }
+
+    fn mul(&self, a: Intcode, b: Intcode) -> Result<Intcode, IntcodeError> {
+        if self.checked {
+            a.checked_mul(b).ok_or_else(|| self.overflow())
+        } else {
+            Ok(a * b)
+        }
+    }
+
+    fn relative(&self, offset: Intcode) -> Result<Intcode, IntcodeError> {
+        self.add(self.base_offset as Intcode, offset)
+    }
+
+    fn address(&self, location: Intcode) -> Result<usize, IntcodeError> {
+        if location < 0 {
+            return Err(IntcodeError::NegativeAddress {
+                pc: self.current_instruction,
+                address: location,
+            });
+        }
+        Ok(location as usize)
+    }
+
+    fn read(&self, param: Param) -> Result<Intcode, IntcodeError> {
+        let location = match param.mode {
+            ParamMode::Position => param.value,
+            ParamMode::Immediate => return Ok(param.value),
+            ParamMode::Relative => self.relative(param.value)?,
+        };
+
+        // like `Computer`, a negative address reads as 0
+        Ok(if location < 0 {
+            0
+        } else {
+            self.memory.read(location as usize)
+        })
+    }
+
+    fn write(&mut self, param: Param, value: Intcode) -> Result<Flow, IntcodeError> {
+        let address = match param.mode {
+            ParamMode::Position => self.address(param.value)?,
+            ParamMode::Immediate => {
+                return Err(IntcodeError::ImmediateWrite {
+                    pc: self.current_instruction,
+                })
+            }
+            ParamMode::Relative => self.address(self.relative(param.value)?)?,
+        };
+
+        self.memory.write(address, value);
+        Ok(if self.invalidate(address) {
+            Flow::Invalidated
+        } else {
+            Flow::Next
+        })
+    }
+
+    fn jump_if(&self, condition: bool, target: Param) -> Result<Flow, IntcodeError> {
+        let target = self.read(target)?;
+        if condition {
+            Ok(Flow::Jump(self.address(target)?))
+        } else {
+            Ok(Flow::Next)
+        }
+    }
+
+    fn execute(&mut self, step: &Step) -> Result<Flow, IntcodeError> {
+        match step.op {
+            Op::Add(a, b, c) => {
+                let sum = self.add(self.read(a)?, self.read(b)?)?;
+                self.write(c, sum)
+            }
+            Op::Mul(a, b, c) => {
+                let product = self.mul(self.read(a)?, self.read(b)?)?;
+                self.write(c, product)
+            }
+            Op::Input(a) => match self.input.front().copied() {
+                Some(value) => {
+                    let flow = self.write(a, value)?;
+                    // like the interpreter, only taken once stored
+                    self.input.pop_front();
+                    Ok(flow)
+                }
+                None => {
+                    self.program_counter = step.pc;
+                    Ok(Flow::Stop(ComputerStatus::WaitingForInput))
+                }
+            },
+            Op::Output(a) => {
+                let value = self.read(a)?;
+                self.output.push_back(value);
+                self.program_counter = step.next;
+                Ok(Flow::Stop(ComputerStatus::ReturnedValue))
+            }
+            Op::JumpIfTrue(a, b) => {
+                let condition = self.read(a)? != 0;
+                self.jump_if(condition, b)
+            }
+            Op::JumpIfFalse(a, b) => {
+                let condition = self.read(a)? == 0;
+                self.jump_if(condition, b)
+            }
+            Op::LessThan(a, b, c) => {
+                let flag = (self.read(a)? < self.read(b)?) as Intcode;
+                self.write(c, flag)
+            }
+            Op::Equals(a, b, c) => {
+                let flag = (self.read(a)? == self.read(b)?) as Intcode;
+                self.write(c, flag)
+            }
+            Op::AdjustBase(a) => {
+                let base = self.relative(self.read(a)?)?;
+                self.base_offset = base as isize;
+                Ok(Flow::Next)
+            }
+            Op::Halt => {
+                // stay on the halt so running again keeps halting
+                self.program_counter = step.pc;
+                self.halted = true;
+                Ok(Flow::Stop(ComputerStatus::Halt))
+            }
+            Op::Fault(ref e) => Err(e.clone()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{read_input, Computer};
+    use std::fs::File;
+    use std::path::PathBuf;
+
+    /// Run both engines side by side, feeding `inputs` as they are asked for, and check they
+    /// stop the same way with the same state every time
+    fn differential(program: &[Intcode], inputs: &[Intcode]) -> Vec<Intcode> {
+        let mut reference = Computer::new(program);
+        let mut blocks = BlockComputer::new(program);
+        let mut inputs = inputs.iter();
+        let mut outputs = Vec::new();
+
+        loop {
+            let expected = reference.try_run();
+            let actual = blocks.try_run();
+            assert_eq!(actual, expected);
+            assert_eq!(blocks.program_counter(), reference.program_counter());
+            assert_eq!(blocks.base_offset(), reference.base_offset());
+            assert_eq!(blocks.memory(), reference.memory());
+            assert_eq!(blocks.output, reference.output);
+            assert_eq!(blocks.input(), reference.input());
+            outputs.extend(reference.output.drain(..));
+            blocks.output.clear();
+
+            match expected {
+                Ok(ComputerStatus::ReturnedValue) | Ok(ComputerStatus::BudgetExhausted) => (),
+                Ok(ComputerStatus::WaitingForInput) => match inputs.next() {
+                    Some(&value) => {
+                        reference.input_add(value);
+                        blocks.input_add(value);
+                    }
+                    None => return outputs,
+                },
+                Ok(ComputerStatus::Halt) | Err(_) => return outputs,
+            }
+        }
+    }
+
+    fn load(day: &str) -> Vec<Intcode> {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("..")
+            .join(day)
+            .join("input.txt");
+        read_input(&mut File::open(path).expect("unable to open input.txt")).expect("parse error")
+    }
+
+    #[test]
+    fn samples() {
+        let sample_4 = [
+            3, 21, 1008, 21, 8, 20, 1005, 20, 22, 107, 8, 21, 20, 1006, 20, 31, 1106, 0, 36, 98, 0,
+            0, 1002, 21, 125, 20, 4, 20, 1105, 1, 46, 104, 999, 1105, 1, 46, 1101, 1000, 1, 20, 4,
+            20, 1105, 1, 46, 98, 99,
+        ];
+        for (input, output) in [(7, 999), (8, 1000), (9, 1001)].iter() {
+            assert_eq!(differential(&sample_4, &[*input]), vec![*output]);
+        }
+
+        let quine = [
+            109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99,
+        ];
+        assert_eq!(differential(&quine, &[]), quine.to_vec());
+
+        for program in [
+            vec![42],
+            vec![104, 1, -3],
+            vec![301, 0, 0, 0],
+            vec![1, 0, 0, -1],
+            vec![10001, 0, 0, 0],
+            vec![1105, 1, -7],
+            vec![109, -1, 204, 1, 99],
+            vec![3, 0, 3, 1, 99],
+            vec![203, -1, 99],
+            vec![1105, 1, 1_000_000_000_000],
+        ]
+        .iter()
+        {
+            differential(program, &[1]);
+        }
+    }
+
+    #[test]
+    fn days() {
+        differential(&load("aoc_05"), &[1]);
+        differential(&load("aoc_05"), &[5]);
+        differential(&load("aoc_09"), &[1]);
+        differential(&load("aoc_09"), &[2]);
+        differential(&load("aoc_13"), &[0; 10_000]);
+    }
+
+    #[test]
+    fn self_modifying() {
+        // patches the operand of the OUT right after it, inside the same block
+        let program = [1101, 7, 0, 5, 104, 0, 99];
+        assert_eq!(differential(&program, &[]), vec![7]);
+
+        // writes a halt over its own jump back to the start
+        let program = [1101, 0, 99, 4, 1105, 1, 0];
+        differential(&program, &[]);
+
+        let mut c = BlockComputer::new(&program);
+        assert_eq!(c.run(), ComputerStatus::Halt);
+        assert_eq!(c.program_counter(), 4);
+    }
+
+    #[test]
+    fn stepping() {
+        let program = load("aoc_09");
+        let mut reference = Computer::new(&program);
+        let mut blocks = BlockComputer::new(&program);
+        reference.input_add(1);
+        blocks.input_add(1);
+
+        while !reference.halted {
+            assert_eq!(blocks.step(), reference.step());
+            assert_eq!(blocks.program_counter(), reference.program_counter());
+        }
+        assert_eq!(blocks.output, reference.output);
+    }
+}
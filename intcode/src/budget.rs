@@ -0,0 +1,148 @@
+use crate::trace::{NoTrace, Tracer};
+use crate::{Computer, ComputerStatus, IntcodeError, Memory, Word};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Asks a run to stop from another thread. Clones share the same flag.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Limits on a run
+#[derive(Debug, Clone, Default)]
+pub struct Budget {
+    /// Instructions left to execute, unlimited if None
+    pub steps: Option<usize>,
+    pub cancel: Option<CancelToken>,
+}
+
+impl Budget {
+    fn exhausted(&self) -> bool {
+        self.steps == Some(0) || self.cancel.as_ref().is_some_and(CancelToken::is_cancelled)
+    }
+}
+
+impl<W: Word, M: Memory<W>> Computer<W, M> {
+    /// Like `try_run`, but stops with `ComputerStatus::BudgetExhausted` once `budget` has no
+    /// steps left or is cancelled. Instructions executed are taken off `budget.steps`, and
+    /// running again with more steps carries on where it stopped.
+    pub fn try_run_budget(&mut self, budget: &mut Budget) -> Result<ComputerStatus, IntcodeError> {
+        self.try_run_budget_traced(budget, &mut NoTrace)
+    }
+
+    pub fn try_run_budget_traced<T: Tracer<W> + ?Sized>(
+        &mut self,
+        budget: &mut Budget,
+        tracer: &mut T,
+    ) -> Result<ComputerStatus, IntcodeError> {
+        loop {
+            if budget.exhausted() {
+                return Ok(ComputerStatus::BudgetExhausted);
+            }
+
+            let halted = self.halted;
+            let status = self.step_traced(tracer)?;
+            // neither waiting for input nor repeating a halt runs an instruction
+            let ran = match status {
+                Some(ComputerStatus::WaitingForInput) => false,
+                Some(ComputerStatus::Halt) => !halted,
+                _ => true,
+            };
+            if ran {
+                if let Some(steps) = &mut budget.steps {
+                    *steps -= 1;
+                }
+            }
+            if let Some(status) = status {
+                return Ok(status);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Computer, Intcode};
+
+    // counts up in [7] forever
+    const SPIN: [Intcode; 8] = [1001, 7, 1, 7, 1105, 1, 0, 0];
+
+    #[test]
+    fn resumes() {
+        let mut c = Computer::new(&[3, 7, 4, 7, 1105, 1, 0, 0]);
+        let mut budget = Budget {
+            steps: Some(2),
+            ..Budget::default()
+        };
+
+        assert_eq!(
+            c.try_run_budget(&mut budget),
+            Ok(ComputerStatus::WaitingForInput)
+        );
+        assert_eq!(budget.steps, Some(2));
+        c.input_add(5);
+        assert_eq!(
+            c.try_run_budget(&mut budget),
+            Ok(ComputerStatus::ReturnedValue)
+        );
+        assert_eq!(
+            c.try_run_budget(&mut budget),
+            Ok(ComputerStatus::BudgetExhausted)
+        );
+        assert_eq!(c.program_counter(), 4);
+
+        budget.steps = Some(10);
+        assert_eq!(
+            c.try_run_budget(&mut budget),
+            Ok(ComputerStatus::WaitingForInput)
+        );
+        assert_eq!(budget.steps, Some(9));
+        assert_eq!(c.output, vec![5]);
+    }
+
+    #[test]
+    fn halted() {
+        let mut c = Computer::new(&[99]);
+        let mut budget = Budget {
+            steps: Some(2),
+            ..Budget::default()
+        };
+
+        for _ in 0..3 {
+            assert_eq!(c.try_run_budget(&mut budget), Ok(ComputerStatus::Halt));
+        }
+        assert_eq!(budget.steps, Some(1));
+    }
+
+    #[test]
+    fn cancelled() {
+        let mut c = Computer::new(&SPIN);
+        let cancel = CancelToken::new();
+        let mut budget = Budget {
+            steps: None,
+            cancel: Some(cancel.clone()),
+        };
+
+        let handle = std::thread::spawn(move || {
+            while c.try_run_budget(&mut budget) != Ok(ComputerStatus::BudgetExhausted) {}
+            c
+        });
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        cancel.cancel();
+        assert!(handle.join().unwrap().memory()[7] > 0);
+    }
+}
@@ -0,0 +1,287 @@
+use crate::disasm::{disassemble, reachable, return_address, Line, Operand, Value};
+use crate::{Instruction, Intcode, Opcode, ParamMode};
+use std::collections::BTreeSet;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EdgeKind {
+    /// Straight on, including a conditional jump not taken
+    Fallthrough,
+    Taken,
+    /// From a call to where it returns, as control comes back through an indirect jump
+    Return,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Edge {
+    pub from: usize,
+    pub to: usize,
+    pub kind: EdgeKind,
+}
+
+/// Instructions only ever entered at the top and left at the bottom
+#[derive(Debug, Clone, PartialEq)]
+pub struct Block {
+    pub start: usize,
+    pub end: usize,
+    pub lines: Vec<Line>,
+    /// Ends in a jump whose target is only known at run time
+    pub indirect: bool,
+    pub halts: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cfg {
+    /// In address order
+    pub blocks: Vec<Block>,
+    pub edges: Vec<Edge>,
+}
+
+fn immediate(operand: &Operand, program: &[Intcode], slot: usize) -> Option<Intcode> {
+    match (operand.mode, &operand.value) {
+        (ParamMode::Immediate, Value::Number(n)) => Some(*n),
+        (ParamMode::Immediate, Value::Label(_)) => Some(program[slot]),
+        _ => None,
+    }
+}
+
+impl Cfg {
+    /// Split the code reachable from address 0 into basic blocks. Jumps are followed when
+    /// their target is immediate, others are marked indirect.
+    pub fn build(program: &[Intcode]) -> Self {
+        let (starts, references) = reachable(program);
+        let listing = disassemble(program);
+
+        let mut leaders: BTreeSet<usize> = references
+            .iter()
+            .map(|&slot| program[slot] as usize)
+            .filter(|target| starts.contains_key(target))
+            .collect();
+        leaders.insert(0);
+        for (&pc, ins) in &starts {
+            if matches!(ins.opcode, Opcode::JumpIfTrue | Opcode::JumpIfFalse) {
+                leaders.insert(pc + ins.size());
+            }
+        }
+
+        let mut blocks: Vec<Block> = Vec::new();
+        let mut open = false;
+        for line in listing.lines {
+            let (address, opcode) = match line {
+                Line::Instruction {
+                    address, opcode, ..
+                } => (address, opcode),
+                Line::Data { .. } => {
+                    open = false;
+                    continue;
+                }
+            };
+
+            match blocks.last_mut() {
+                Some(block) if open && !leaders.contains(&address) => {
+                    block.end += line.size();
+                    block.lines.push(line);
+                }
+                _ => blocks.push(Block {
+                    start: address,
+                    end: address + line.size(),
+                    lines: vec![line],
+                    indirect: false,
+                    halts: false,
+                }),
+            }
+
+            open = !matches!(
+                opcode,
+                Opcode::JumpIfTrue | Opcode::JumpIfFalse | Opcode::Halt
+            );
+        }
+
+        let mut edges = Vec::new();
+        for block in &mut blocks {
+            let (pc, opcode, operands) = match block.lines.last() {
+                Some(Line::Instruction {
+                    address,
+                    opcode,
+                    operands,
+                }) => (*address, *opcode, operands.clone()),
+                _ => continue,
+            };
+            let (from, end) = (block.start, block.end);
+            let mut edge = |to: usize, kind| {
+                if starts.contains_key(&to) {
+                    edges.push(Edge { from, to, kind });
+                }
+            };
+
+            match opcode {
+                Opcode::Halt => block.halts = true,
+                Opcode::JumpIfTrue | Opcode::JumpIfFalse => {
+                    let jumps = immediate(&operands[0], program, pc + 1)
+                        .map(|cond| (cond != 0) == (opcode == Opcode::JumpIfTrue));
+                    if jumps != Some(false) {
+                        match immediate(&operands[1], program, pc + 2) {
+                            Some(target) if target >= 0 => edge(target as usize, EdgeKind::Taken),
+                            Some(_) => (),
+                            None => block.indirect = true,
+                        }
+                    }
+                    if jumps != Some(true) {
+                        edge(end, EdgeKind::Fallthrough);
+                    }
+
+                    // a call saves its return address just before jumping
+                    if let [.., Line::Instruction { address, .. }, _] = block.lines[..] {
+                        let ins = Instruction::decode(address, program[address])
+                            .expect("decoded by the disassembler");
+                        if let Some(ret) = return_address(program, address, &ins) {
+                            edge(ret as usize, EdgeKind::Return);
+                        }
+                    }
+                }
+                _ => edge(end, EdgeKind::Fallthrough),
+            }
+        }
+
+        Cfg { blocks, edges }
+    }
+
+    /// The block holding `address`
+    pub fn block_at(&self, address: usize) -> Option<&Block> {
+        let idx = self
+            .blocks
+            .partition_point(|block| block.start <= address)
+            .checked_sub(1)?;
+        Some(&self.blocks[idx]).filter(|block| address < block.end)
+    }
+
+    pub fn successors(&self, start: usize) -> impl Iterator<Item = &Edge> {
+        self.edges.iter().filter(move |edge| edge.from == start)
+    }
+
+    /// The graph in Graphviz DOT format
+    pub fn dot(&self) -> Dot<'_> {
+        Dot { cfg: self }
+    }
+}
+
+pub struct Dot<'a> {
+    cfg: &'a Cfg,
+}
+
+impl fmt::Display for Dot<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "digraph cfg {{")?;
+        writeln!(f, "    node [shape=box, fontname=\"monospace\"];")?;
+
+        for block in &self.cfg.blocks {
+            let label: String = block
+                .lines
+                .iter()
+                .map(|line| format!("{:>5}  {}\\l", line.address(), line.to_string().trim_end()))
+                .collect();
+            let style = if block.indirect {
+                ", color=red"
+            } else if block.halts {
+                ", peripheries=2"
+            } else {
+                ""
+            };
+            writeln!(f, "    b{} [label=\"{}\"{}];", block.start, label, style)?;
+        }
+
+        for edge in &self.cfg.edges {
+            let style = match edge.kind {
+                EdgeKind::Fallthrough => "",
+                EdgeKind::Taken => " [label=\"taken\"]",
+                EdgeKind::Return => " [style=dotted]",
+            };
+            writeln!(f, "    b{} -> b{}{};", edge.from, edge.to, style)?;
+        }
+
+        writeln!(f, "}}")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn sample_4() {
+        let program = [
+            3, 21, 1008, 21, 8, 20, 1005, 20, 22, 107, 8, 21, 20, 1006, 20, 31, 1106, 0, 36, 98, 0,
+            0, 1002, 21, 125, 20, 4, 20, 1105, 1, 46, 104, 999, 1105, 1, 46, 1101, 1000, 1, 20, 4,
+            20, 1105, 1, 46, 98, 99,
+        ];
+        let cfg = Cfg::build(&program);
+
+        let bounds: Vec<_> = cfg.blocks.iter().map(|b| (b.start, b.end)).collect();
+        assert_eq!(
+            bounds,
+            vec![
+                (0, 9),
+                (9, 16),
+                (16, 19),
+                (22, 31),
+                (31, 36),
+                (36, 45),
+                (46, 47)
+            ]
+        );
+        assert_eq!(
+            cfg.successors(0).collect::<Vec<_>>(),
+            vec![
+                &Edge {
+                    from: 0,
+                    to: 22,
+                    kind: EdgeKind::Taken
+                },
+                &Edge {
+                    from: 0,
+                    to: 9,
+                    kind: EdgeKind::Fallthrough
+                },
+            ]
+        );
+        // JF #0 always jumps
+        assert_eq!(cfg.successors(16).count(), 1);
+        assert!(cfg.blocks[6].halts);
+        assert_eq!(cfg.block_at(40).map(|b| b.start), Some(36));
+        assert_eq!(cfg.block_at(20), None);
+    }
+
+    #[test]
+    fn calls() {
+        // call f, which returns through rb+0
+        let program = [21101, 7, 0, 0, 1105, 1, 8, 99, 2106, 0, 0];
+        let cfg = Cfg::build(&program);
+
+        assert!(cfg.blocks.iter().find(|b| b.start == 8).unwrap().indirect);
+        assert_eq!(
+            cfg.dot().to_string(),
+            r#"digraph cfg {
+    node [shape=box, fontname="monospace"];
+    b0 [label="    0  ADD  #L0007, #0, rb+0\l    4  JT   #1, #L0008\l"];
+    b7 [label="    7  HLT\l", peripheries=2];
+    b8 [label="    8  JF   #0, rb+0\l", color=red];
+    b0 -> b8 [label="taken"];
+    b0 -> b7 [style=dotted];
+}
+"#
+        );
+    }
+
+    #[test]
+    fn arcade() {
+        let path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../aoc_13/input.txt");
+        let program = crate::read_input(&mut std::fs::File::open(path).unwrap()).unwrap();
+        let cfg = Cfg::build(&program);
+
+        assert!(cfg.blocks.len() > 20);
+        assert!(cfg.blocks.iter().any(|b| b.indirect));
+        for edge in &cfg.edges {
+            assert_eq!(cfg.block_at(edge.to).map(|b| b.start), Some(edge.to));
+        }
+    }
+}
@@ -0,0 +1,276 @@
+use crate::disasm::{disassemble_from, Line};
+use crate::trace::{Event, Tracer};
+use crate::{Computer, Instruction, Intcode, Memory, Opcode, ParamMode, Word};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+/// Which ways a conditional jump went
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Branch {
+    pub taken: bool,
+    pub not_taken: bool,
+}
+
+/// Tracer recording executed addresses and branch directions, also available as a mode with
+/// `Computer::cover`. Pass the same collector to several runs, or `merge` collectors, to get
+/// the coverage of a whole test suite.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Coverage {
+    executed: BTreeSet<usize>,
+    branches: BTreeMap<usize, Branch>,
+    /// Where jumps went, to find code only reached through an indirect jump
+    targets: BTreeSet<usize>,
+}
+
+impl Coverage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn merge(&mut self, other: &Coverage) {
+        self.executed.extend(&other.executed);
+        self.targets.extend(&other.targets);
+        for (&pc, branch) in &other.branches {
+            let merged = self.branches.entry(pc).or_default();
+            merged.taken |= branch.taken;
+            merged.not_taken |= branch.not_taken;
+        }
+    }
+
+    pub fn executed(&self, address: usize) -> bool {
+        self.executed.contains(&address)
+    }
+
+    pub fn branch(&self, address: usize) -> Option<Branch> {
+        self.branches.get(&address).copied()
+    }
+
+    pub(crate) fn instruction(&mut self, pc: usize) {
+        self.executed.insert(pc);
+    }
+
+    pub(crate) fn jump(&mut self, to: usize) {
+        self.targets.insert(to);
+    }
+
+    /// Record which way a conditional jump went
+    pub(crate) fn branched(&mut self, pc: usize, instruction: &Instruction, jumps: bool) {
+        if instruction.modes[0] == ParamMode::Immediate {
+            // an unconditional jump, which can only go one way
+            return;
+        }
+
+        let branch = self.branches.entry(pc).or_default();
+        if jumps {
+            branch.taken = true;
+        } else {
+            branch.not_taken = true;
+        }
+    }
+
+    /// Disassembly of `program` marking each instruction executed (`+`) or not (`-`)
+    pub fn annotate<'a>(&'a self, program: &'a [Intcode]) -> Annotated<'a> {
+        Annotated {
+            coverage: self,
+            program,
+        }
+    }
+}
+
+impl<W: Word> Tracer<W> for Coverage {
+    fn trace(&mut self, event: &Event<W>) {
+        match event {
+            Event::Instruction {
+                pc,
+                instruction,
+                operands,
+            } => {
+                self.instruction(*pc);
+                match instruction.opcode {
+                    Opcode::JumpIfTrue => self.branched(*pc, instruction, !operands[0].is_zero()),
+                    Opcode::JumpIfFalse => self.branched(*pc, instruction, operands[0].is_zero()),
+                    _ => (),
+                }
+            }
+            Event::Jump { to, .. } => self.jump(*to),
+            _ => (),
+        }
+    }
+}
+
+impl<W: Word, M: Memory<W>> Computer<W, M> {
+    /// Collect coverage from now on, without passing a tracer to each run
+    pub fn cover(&mut self) {
+        self.coverage = Some(Box::new(Coverage::new()));
+    }
+
+    pub fn coverage(&self) -> Option<&Coverage> {
+        self.coverage.as_deref()
+    }
+}
+
+pub struct Annotated<'a> {
+    coverage: &'a Coverage,
+    program: &'a [Intcode],
+}
+
+impl fmt::Display for Annotated<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let entries: Vec<_> = std::iter::once(0)
+            .chain(self.coverage.targets.iter().copied())
+            .collect();
+        let listing = disassemble_from(self.program, &entries);
+        let instructions: Vec<_> = listing
+            .lines
+            .iter()
+            .filter(|l| matches!(l, Line::Instruction { .. }))
+            .collect();
+        let executed = instructions
+            .iter()
+            .filter(|l| self.coverage.executed(l.address()))
+            .count();
+        let one_way = self
+            .coverage
+            .branches
+            .values()
+            .filter(|b| b.taken != b.not_taken)
+            .count();
+        writeln!(
+            f,
+            "; {}/{} instructions executed, {} {} taken only one way",
+            executed,
+            instructions.len(),
+            one_way,
+            if one_way == 1 { "branch" } else { "branches" }
+        )?;
+
+        for line in &listing.lines {
+            let address = line.address();
+            let marker = match line {
+                Line::Instruction { .. } if self.coverage.executed(address) => '+',
+                Line::Instruction { .. } => '-',
+                Line::Data { .. } => ' ',
+            };
+            let note = match self.coverage.branch(address) {
+                Some(Branch {
+                    taken: true,
+                    not_taken: false,
+                }) => "  ; always taken",
+                Some(Branch {
+                    taken: false,
+                    not_taken: true,
+                }) => "  ; never taken",
+                _ => "",
+            };
+            let label = listing
+                .labels
+                .get(&address)
+                .map(|l| format!("{}:", l))
+                .unwrap_or_default();
+
+            let body = format!("{:<8}{}", label, line);
+            writeln!(f, "{} {:>5}  {}{}", marker, address, body.trim_end(), note)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Computer, ComputerStatus};
+
+    const SAMPLE_4: [Intcode; 47] = [
+        3, 21, 1008, 21, 8, 20, 1005, 20, 22, 107, 8, 21, 20, 1006, 20, 31, 1106, 0, 36, 98, 0, 0,
+        1002, 21, 125, 20, 4, 20, 1105, 1, 46, 104, 999, 1105, 1, 46, 1101, 1000, 1, 20, 4, 20,
+        1105, 1, 46, 98, 99,
+    ];
+
+    fn run(input: Intcode) -> Coverage {
+        let mut coverage = Coverage::new();
+        let mut c = Computer::new(&SAMPLE_4);
+        c.input_add(input);
+        while c.try_run_traced(&mut coverage).unwrap() != ComputerStatus::Halt {}
+
+        // the same as a mode
+        let mut c = Computer::new(&SAMPLE_4);
+        c.input_add(input);
+        c.cover();
+        while c.run() != ComputerStatus::Halt {}
+        assert_eq!(c.coverage(), Some(&coverage));
+
+        coverage
+    }
+
+    #[test]
+    fn merged() {
+        let mut coverage = run(7);
+        coverage.merge(&run(9));
+
+        assert!(coverage.executed(31));
+        assert!(!coverage.executed(22));
+        assert_eq!(
+            coverage.branch(6),
+            Some(Branch {
+                taken: false,
+                not_taken: true
+            })
+        );
+        assert_eq!(
+            coverage.annotate(&SAMPLE_4).to_string(),
+            "; 12/15 instructions executed, 1 branch taken only one way
++     0          IN   [21]
++     2          EQ   [21], #8, [20]
++     6          JT   [20], #L0022  ; never taken
++     9          LT   #8, [21], [20]
++    13          JF   [20], #L0031
++    16          JF   #0, #L0036
+     19          DATA 98, 0, 0
+-    22  L0022:  MUL  [21], #125, [20]
+-    26          OUT  [20]
+-    28          JT   #1, #L0046
++    31  L0031:  OUT  #999
++    33          JT   #1, #L0046
++    36  L0036:  ADD  #1000, #1, [20]
++    40          OUT  [20]
++    42          JT   #1, #L0046
+     45          DATA 98
++    46  L0046:  HLT
+"
+        );
+
+        coverage.merge(&run(8));
+        assert!(coverage.executed(22));
+        assert!(coverage
+            .annotate(&SAMPLE_4)
+            .to_string()
+            .starts_with("; 15/15 instructions executed, 0 branches taken only one way\n"));
+        assert_eq!(
+            coverage.branch(6),
+            Some(Branch {
+                taken: true,
+                not_taken: true
+            })
+        );
+    }
+
+    #[test]
+    fn indirect() {
+        // jumps to the address held in [8]
+        let program = [105, 1, 8, 99, 0, 0, 0, 0, 9, 104, 7, 99];
+        let mut c = Computer::new(&program);
+        c.cover();
+        while c.run() != ComputerStatus::Halt {}
+
+        assert_eq!(
+            c.coverage().unwrap().annotate(&program).to_string(),
+            "; 3/3 instructions executed, 0 branches taken only one way
++     0          JT   #1, [8]
+      3          DATA 99, 0, 0, 0, 0, 9
++     9          OUT  #7
++    11          HLT
+"
+        );
+    }
+}
@@ -0,0 +1,283 @@
+use crate::{Computer, ComputerStatus, Intcode, IntcodeError, Memory, Snapshot, SnapshotError};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Stop {
+    Breakpoint(usize),
+    Watchpoint {
+        address: usize,
+        old: Intcode,
+        new: Intcode,
+    },
+    WaitingForInput,
+    Halt,
+    Error(IntcodeError),
+}
+
+impl fmt::Display for Stop {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Stop::Breakpoint(pc) => write!(f, "breakpoint at {}", pc),
+            Stop::Watchpoint { address, old, new } => {
+                write!(f, "watchpoint [{}]: {} -> {}", address, old, new)
+            }
+            Stop::WaitingForInput => write!(f, "waiting for input"),
+            Stop::Halt => write!(f, "halted"),
+            Stop::Error(e) => write!(f, "error: {}", e),
+        }
+    }
+}
+
+pub struct Debugger {
+    pub computer: Computer,
+    breakpoints: BTreeSet<usize>,
+    watchpoints: BTreeMap<usize, Intcode>,
+    /// Breakpoint reported at the program counter, so resuming does not stop on it again
+    at_breakpoint: Option<usize>,
+}
+
+impl Debugger {
+    pub fn new(program: &[Intcode]) -> Self {
+        Self {
+            computer: Computer::new(program),
+            breakpoints: BTreeSet::new(),
+            watchpoints: BTreeMap::new(),
+            at_breakpoint: None,
+        }
+    }
+
+    pub fn peek(&self, address: usize) -> Intcode {
+        self.computer.memory().read(address)
+    }
+
+    pub fn add_breakpoint(&mut self, pc: usize) -> bool {
+        self.breakpoints.insert(pc)
+    }
+
+    pub fn remove_breakpoint(&mut self, pc: usize) -> bool {
+        self.breakpoints.remove(&pc)
+    }
+
+    pub fn breakpoints(&self) -> impl Iterator<Item = &usize> {
+        self.breakpoints.iter()
+    }
+
+    pub fn watch(&mut self, address: usize) -> bool {
+        let value = self.peek(address);
+        self.watchpoints.insert(address, value).is_none()
+    }
+
+    pub fn unwatch(&mut self, address: usize) -> bool {
+        self.watchpoints.remove(&address).is_some()
+    }
+
+    pub fn watchpoints(&self) -> impl Iterator<Item = &usize> {
+        self.watchpoints.keys()
+    }
+
+    /// Undo the last recorded instruction, see `Computer::step_back`
+    pub fn step_back(&mut self) -> bool {
+        let moved = self.computer.step_back();
+        self.replaced();
+        moved
+    }
+
+    /// Go to when `count` recorded instructions had run, see `Computer::seek`
+    pub fn seek(&mut self, count: usize) -> Result<Option<ComputerStatus>, IntcodeError> {
+        let result = self.computer.seek(count);
+        self.replaced();
+        result
+    }
+
+    pub fn restore(&mut self, snapshot: Snapshot) -> Result<(), SnapshotError> {
+        self.computer.restore(snapshot)?;
+        self.replaced();
+        Ok(())
+    }
+
+    /// Forget what was seen before the machine state was swapped out under us
+    fn replaced(&mut self) {
+        let memory = self.computer.memory();
+        for (&address, old) in self.watchpoints.iter_mut() {
+            *old = memory.read(address);
+        }
+        self.at_breakpoint = None;
+    }
+
+    fn check_watchpoints(&mut self) -> Option<Stop> {
+        let memory = self.computer.memory();
+        let mut stop = None;
+
+        for (&address, old) in self.watchpoints.iter_mut() {
+            let new = memory.read(address);
+            if new != *old && stop.is_none() {
+                stop = Some(Stop::Watchpoint {
+                    address,
+                    old: *old,
+                    new,
+                });
+            }
+            *old = new;
+        }
+
+        stop
+    }
+
+    /// Execute one instruction, reporting anything that should hand control back to the user
+    pub fn step(&mut self) -> Option<Stop> {
+        self.execute().or_else(|| {
+            let pc = self.computer.program_counter();
+            if self.breakpoints.contains(&pc) {
+                self.at_breakpoint = Some(pc);
+                Some(Stop::Breakpoint(pc))
+            } else {
+                None
+            }
+        })
+    }
+
+    fn execute(&mut self) -> Option<Stop> {
+        match self.computer.step() {
+            Err(e) => return Some(Stop::Error(e)),
+            Ok(Some(ComputerStatus::Halt)) => return Some(Stop::Halt),
+            Ok(Some(ComputerStatus::WaitingForInput)) => return Some(Stop::WaitingForInput),
+            Ok(Some(ComputerStatus::ReturnedValue))
+            | Ok(Some(ComputerStatus::BudgetExhausted))
+            | Ok(None) => (),
+        }
+
+        // only an instruction that ran leaves the reported breakpoint behind
+        self.at_breakpoint = None;
+        self.check_watchpoints()
+    }
+
+    /// Run until something stops it. A breakpoint is checked before its instruction runs, so
+    /// one on the current program counter is hit, unless it is the one just reported.
+    pub fn cont(&mut self) -> Stop {
+        loop {
+            let pc = self.computer.program_counter();
+            if self.breakpoints.contains(&pc) && self.at_breakpoint != Some(pc) {
+                self.at_breakpoint = Some(pc);
+                return Stop::Breakpoint(pc);
+            }
+            if let Some(stop) = self.execute() {
+                return stop;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::asm::assemble;
+
+    fn counter() -> Vec<Intcode> {
+        assemble(
+            "
+                    in   [limit]
+            loop:   add  [count], #1, [count]
+                    out  [count]
+                    lt   [count], [limit], [tmp]
+                    jt   [tmp], #loop
+                    hlt
+            limit:  data 0
+            count:  data 0
+            tmp:    data 0
+            ",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn breakpoints() {
+        let mut d = Debugger::new(&counter());
+        assert!(d.add_breakpoint(2));
+        assert_eq!(d.cont(), Stop::WaitingForInput);
+        d.computer.input_add(3);
+
+        for _ in 0..2 {
+            assert_eq!(d.cont(), Stop::Breakpoint(2));
+        }
+        assert!(d.remove_breakpoint(2));
+        assert_eq!(d.cont(), Stop::Halt);
+        assert_eq!(d.computer.output, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn entry_breakpoint() {
+        let mut d = Debugger::new(&counter());
+        d.computer.input_add(2);
+        assert!(d.add_breakpoint(0));
+        assert_eq!(d.cont(), Stop::Breakpoint(0));
+        assert_eq!(d.computer.program_counter(), 0);
+        assert_eq!(d.cont(), Stop::Halt);
+        assert_eq!(d.computer.output, vec![1, 2]);
+
+        // waiting for input does not run the instruction under the breakpoint
+        let mut d = Debugger::new(&counter());
+        assert!(d.add_breakpoint(0));
+        assert_eq!(d.cont(), Stop::Breakpoint(0));
+        assert_eq!(d.cont(), Stop::WaitingForInput);
+        d.computer.input_add(1);
+        assert_eq!(d.cont(), Stop::Halt);
+    }
+
+    #[test]
+    fn watchpoints() {
+        let program = counter();
+        let count = program.len() - 2;
+
+        let mut d = Debugger::new(&program);
+        d.computer.record_history();
+        d.computer.input_add(2);
+        assert!(d.watch(count));
+        assert_eq!(
+            d.cont(),
+            Stop::Watchpoint {
+                address: count,
+                old: 0,
+                new: 1
+            }
+        );
+        assert_eq!(d.computer.program_counter(), 6);
+
+        // rewinding the write puts the watched value back, so it changes again
+        let before = d.computer.snapshot();
+        assert!(d.step_back());
+        assert_eq!(d.computer.program_counter(), 2);
+        assert_eq!(
+            d.step(),
+            Some(Stop::Watchpoint {
+                address: count,
+                old: 0,
+                new: 1
+            })
+        );
+        assert!(d.step_back());
+        d.restore(before).unwrap();
+        assert_eq!(d.step(), None);
+
+        assert_eq!(
+            d.cont(),
+            Stop::Watchpoint {
+                address: count,
+                old: 1,
+                new: 2
+            }
+        );
+        assert_eq!(d.cont(), Stop::Halt);
+    }
+
+    #[test]
+    fn error() {
+        let mut d = Debugger::new(&[1101, 1, 1, 5, 0]);
+        assert_eq!(
+            d.cont(),
+            Stop::Error(IntcodeError::UnknownOpcode { pc: 4, opcode: 0 })
+        );
+        assert_eq!(d.peek(5), 2);
+        assert_eq!(d.computer.program_counter(), 4);
+    }
+}
@@ -0,0 +1,852 @@
+use crate::cfg::{Block, Cfg, EdgeKind};
+use crate::disasm::{reachable, return_address};
+use crate::{Instruction, Intcode, Opcode, ParamMode};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Num(Intcode),
+    Var(String),
+    Mem(Box<Expr>),
+    Neg(Box<Expr>),
+    Not(Box<Expr>),
+    Bin(Box<Expr>, &'static str, Box<Expr>),
+}
+
+fn precedence(op: &str) -> u8 {
+    match op {
+        "*" => 3,
+        "+" | "-" => 2,
+        _ => 1,
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Num(n) => write!(f, "{}", n),
+            Expr::Var(name) => write!(f, "{}", name),
+            Expr::Mem(address) => write!(f, "mem[{}]", address),
+            Expr::Neg(e) | Expr::Not(e) => {
+                let sign = if let Expr::Neg(_) = self { "-" } else { "!" };
+                match **e {
+                    Expr::Bin(..) => write!(f, "{}({})", sign, e),
+                    _ => write!(f, "{}{}", sign, e),
+                }
+            }
+            Expr::Bin(a, op, b) => {
+                let wrap = |e: &Expr, right: bool| match e {
+                    Expr::Bin(_, inner, _) => {
+                        precedence(inner) < precedence(op)
+                            || (right && precedence(inner) == precedence(op) && *op != "*")
+                            || (precedence(inner) == 1 && precedence(op) == 1)
+                    }
+                    _ => false,
+                };
+                let side = |e: &Expr, right| {
+                    if wrap(e, right) {
+                        format!("({})", e)
+                    } else {
+                        e.to_string()
+                    }
+                };
+                write!(f, "{} {} {}", side(a, false), op, side(b, true))
+            }
+        }
+    }
+}
+
+impl Expr {
+    fn add(a: Expr, b: Expr) -> Expr {
+        match (a, b) {
+            (Expr::Num(x), Expr::Num(y)) if x.checked_add(y).is_some() => Expr::Num(x + y),
+            (Expr::Num(0), e) | (e, Expr::Num(0)) => e,
+            // constants move to the right, unless both sides are and the sum overflowed
+            (Expr::Num(n), e) if n > 0 && !matches!(e, Expr::Num(_)) => {
+                Expr::Bin(Box::new(e), "+", Box::new(Expr::Num(n)))
+            }
+            (Expr::Num(n), e) if n < 0 && n != Intcode::MIN && !matches!(e, Expr::Num(_)) => {
+                Expr::Bin(Box::new(e), "-", Box::new(Expr::Num(-n)))
+            }
+            (e, Expr::Num(n)) if n < 0 && n != Intcode::MIN => {
+                Expr::Bin(Box::new(e), "-", Box::new(Expr::Num(-n)))
+            }
+            (a, Expr::Neg(b)) | (Expr::Neg(b), a) => Expr::Bin(Box::new(a), "-", b),
+            (a, b) => Expr::Bin(Box::new(a), "+", Box::new(b)),
+        }
+    }
+
+    fn mul(a: Expr, b: Expr) -> Expr {
+        match (a, b) {
+            (Expr::Num(x), Expr::Num(y)) if x.checked_mul(y).is_some() => Expr::Num(x * y),
+            (Expr::Num(0), _) | (_, Expr::Num(0)) => Expr::Num(0),
+            (Expr::Num(1), e) | (e, Expr::Num(1)) => e,
+            (Expr::Num(-1), e) | (e, Expr::Num(-1)) => Expr::Neg(Box::new(e)),
+            (a, b) => Expr::Bin(Box::new(a), "*", Box::new(b)),
+        }
+    }
+
+    fn not(self) -> Expr {
+        match self {
+            Expr::Not(e) => *e,
+            Expr::Num(n) => Expr::Num((n == 0) as Intcode),
+            Expr::Bin(a, op, b) if precedence(op) == 1 => {
+                let flipped = match op {
+                    "<" => ">=",
+                    ">=" => "<",
+                    "==" => "!=",
+                    _ => "==",
+                };
+                Expr::Bin(a, flipped, b)
+            }
+            e => Expr::Not(Box::new(e)),
+        }
+    }
+
+    fn mentions(&self, prefix: &str) -> bool {
+        match self {
+            Expr::Num(_) => false,
+            Expr::Var(name) => name.starts_with(prefix),
+            Expr::Mem(e) | Expr::Neg(e) | Expr::Not(e) => e.mentions(prefix),
+            Expr::Bin(a, _, b) => a.mentions(prefix) || b.mentions(prefix),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Stmt {
+    Label(usize),
+    Assign(Expr, Expr),
+    Output(Expr),
+    Call(usize, Vec<Expr>),
+    AdjustBase(Expr),
+    If(Expr, Vec<Stmt>, Vec<Stmt>),
+    DoWhile(Vec<Stmt>, Expr),
+    Loop(Vec<Stmt>),
+    Break,
+    Continue,
+    Goto(usize),
+    GotoIndirect(Expr),
+    Return,
+    Halt,
+}
+
+/// A function found by following calls, `ARB #frame` being its prologue
+#[derive(Debug, Clone, PartialEq)]
+pub struct Function {
+    pub entry: usize,
+    pub frame: Intcode,
+    pub params: usize,
+    /// Start addresses of its blocks, in address order
+    pub blocks: Vec<usize>,
+}
+
+/// How control leaves a block
+enum Exit {
+    Next,
+    Jump(usize),
+    Branch(Expr, usize),
+    Call(usize, Vec<Expr>),
+    Indirect(Expr),
+    Return,
+    Halt,
+}
+
+struct Context {
+    head: Option<usize>,
+    exit: Option<usize>,
+    next: usize,
+}
+
+/// Turns a program written with the usual relative base calling convention into C-like
+/// pseudocode. Arguments go in `rb+1`, `rb+2`, ... with the return address in `rb+0`; the
+/// callee moves the base past them with `ARB`, so it sees them below `rb`.
+pub struct Decompiler<'a> {
+    program: &'a [Intcode],
+    cfg: Cfg,
+    starts: BTreeMap<usize, Instruction>,
+    /// Operand words the program writes to, e.g. to index an array
+    patched: HashSet<usize>,
+    /// Cells only ever used to hold a comparison for the jump right after it
+    temps: HashSet<usize>,
+    functions: BTreeMap<usize, Function>,
+}
+
+impl<'a> Decompiler<'a> {
+    pub fn new(program: &'a [Intcode]) -> Self {
+        let cfg = Cfg::build(program);
+        let (starts, _) = reachable(program);
+
+        let operand_slots: HashSet<usize> = starts
+            .iter()
+            .flat_map(|(&pc, ins)| pc + 1..pc + ins.size())
+            .collect();
+        let mut patched = HashSet::new();
+        let mut reads: HashMap<usize, bool> = HashMap::new();
+        let mut previous: Option<(usize, Instruction)> = None;
+
+        for (&pc, &ins) in &starts {
+            let count = ins.opcode.params();
+            for idx in 0..count {
+                if ins.modes[idx] != ParamMode::Position {
+                    continue;
+                }
+                let address = program[pc + 1 + idx] as usize;
+                if ins.opcode.writes() && idx == count - 1 {
+                    if operand_slots.contains(&address) {
+                        patched.insert(address);
+                    }
+                    continue;
+                }
+
+                let folded = idx == 0
+                    && matches!(ins.opcode, Opcode::JumpIfTrue | Opcode::JumpIfFalse)
+                    && matches!(previous, Some((prev, p))
+                        if matches!(p.opcode, Opcode::LessThan | Opcode::Equals)
+                            && p.modes[2] == ParamMode::Position
+                            && program[prev + 3] as usize == address);
+                *reads.entry(address).or_insert(true) &= folded;
+            }
+            previous = Some((pc, ins));
+        }
+        let temps = reads
+            .into_iter()
+            .filter(|&(_, folded)| folded)
+            .map(|(address, _)| address)
+            .collect();
+
+        let mut decompiler = Decompiler {
+            program,
+            cfg,
+            starts,
+            patched,
+            temps,
+            functions: BTreeMap::new(),
+        };
+        decompiler.find_functions();
+        decompiler
+    }
+
+    pub fn functions(&self) -> impl Iterator<Item = &Function> {
+        self.functions.values()
+    }
+
+    /// Only for blocks found by `find_functions`, which all start on code
+    fn block(&self, start: usize) -> &Block {
+        self.cfg.block_at(start).expect("block start")
+    }
+
+    fn instructions(&self, block: &Block) -> Vec<(usize, Instruction)> {
+        block
+            .lines
+            .iter()
+            .map(|line| (line.address(), self.starts[&line.address()]))
+            .collect()
+    }
+
+    fn word(&self, pc: usize, idx: usize) -> Intcode {
+        self.program[pc + 1 + idx]
+    }
+
+    fn is_call(&self, start: usize) -> bool {
+        self.cfg
+            .successors(start)
+            .any(|edge| edge.kind == EdgeKind::Return)
+    }
+
+    fn find_functions(&mut self) {
+        let mut entries: BTreeSet<usize> = BTreeSet::new();
+        entries.insert(0);
+        let mut params: HashMap<usize, usize> = HashMap::new();
+
+        for block in &self.cfg.blocks {
+            if !self.is_call(block.start) {
+                continue;
+            }
+            if let Some(callee) = self
+                .cfg
+                .successors(block.start)
+                .find(|edge| edge.kind == EdgeKind::Taken)
+            {
+                entries.insert(callee.to);
+                let count = self.arguments(block).len();
+                let known = params.entry(callee.to).or_insert(0);
+                *known = (*known).max(count);
+            }
+        }
+
+        for &entry in &entries {
+            // e.g. an empty program, or one that does not start with code
+            let first = match self.cfg.block_at(entry) {
+                Some(block) => self.instructions(block)[0],
+                None => continue,
+            };
+
+            let mut seen = BTreeSet::new();
+            let mut work = vec![entry];
+            while let Some(start) = work.pop() {
+                if !seen.insert(start) {
+                    continue;
+                }
+                let call = self.is_call(start);
+                for edge in self.cfg.successors(start) {
+                    let intraprocedural = match edge.kind {
+                        EdgeKind::Taken => !call,
+                        EdgeKind::Fallthrough | EdgeKind::Return => true,
+                    };
+                    if intraprocedural && !entries.contains(&edge.to) {
+                        work.push(edge.to);
+                    }
+                }
+            }
+
+            let frame = match first {
+                (pc, ins) if ins.opcode == Opcode::AdjustBase && entry != 0 => self.word(pc, 0),
+                _ => 0,
+            };
+            self.functions.insert(
+                entry,
+                Function {
+                    entry,
+                    frame,
+                    params: params.get(&entry).copied().unwrap_or(0),
+                    blocks: seen.into_iter().collect(),
+                },
+            );
+        }
+    }
+
+    /// Offsets `rb+i`, i > 0, set up as arguments right before a call, in order
+    fn arguments(&self, block: &Block) -> Vec<usize> {
+        let mut offsets = Vec::new();
+        for (pc, ins) in self.instructions(block).into_iter().rev().skip(2) {
+            let count = ins.opcode.params();
+            if !ins.opcode.writes() || ins.modes[count - 1] != ParamMode::Relative {
+                break;
+            }
+            let offset = self.word(pc, count - 1);
+            if offset <= 0 {
+                break;
+            }
+            offsets.push(offset as usize);
+        }
+        offsets.sort_unstable();
+        offsets.dedup();
+        offsets
+    }
+
+    fn name(&self, function: &Function, offset: Intcode) -> Expr {
+        let slot = match function.frame.checked_add(offset) {
+            Some(slot) => slot,
+            None => return Expr::Var(format!("rb[{}]", offset)),
+        };
+        Expr::Var(if offset > 0 {
+            format!("out{}", offset)
+        } else if offset < 0 && slot >= 1 && slot as usize <= function.params {
+            format!("arg{}", slot)
+        } else if offset < 0 && slot > 0 {
+            format!("local{}", slot as usize - function.params)
+        } else if slot == 0 && function.frame > 0 {
+            "ret".to_string()
+        } else {
+            format!("rb[{}]", offset)
+        })
+    }
+
+    /// The location a parameter refers to: the address for position mode
+    fn location(
+        &self,
+        function: &Function,
+        patches: &HashMap<usize, Expr>,
+        pc: usize,
+        ins: &Instruction,
+        idx: usize,
+    ) -> Expr {
+        let slot = pc + 1 + idx;
+        let word = if self.patched.contains(&slot) {
+            patches
+                .get(&slot)
+                .cloned()
+                .unwrap_or_else(|| Expr::Mem(Box::new(Expr::Num(slot as Intcode))))
+        } else {
+            Expr::Num(self.program[slot])
+        };
+
+        match (ins.modes[idx], word) {
+            (ParamMode::Relative, Expr::Num(offset)) => self.name(function, offset),
+            (ParamMode::Relative, e) => Expr::Var(format!("rb[{}]", e)),
+            (_, e) => e,
+        }
+    }
+
+    fn read(
+        &self,
+        function: &Function,
+        patches: &HashMap<usize, Expr>,
+        pc: usize,
+        ins: &Instruction,
+        idx: usize,
+    ) -> Expr {
+        let location = self.location(function, patches, pc, ins, idx);
+        match (ins.modes[idx], location) {
+            (ParamMode::Position, Expr::Num(address)) => patches
+                .get(&(address as usize))
+                .cloned()
+                .unwrap_or_else(|| Expr::Mem(Box::new(Expr::Num(address)))),
+            (ParamMode::Position, e) => Expr::Mem(Box::new(e)),
+            (_, e) => e,
+        }
+    }
+
+    /// Statements for a block, and how it is left
+    fn translate(&self, function: &Function, block: &Block) -> (Vec<Stmt>, Exit) {
+        let instructions = self.instructions(block);
+        let call = self.is_call(block.start);
+        let arguments = if call { self.arguments(block) } else { vec![] };
+
+        let mut stmts = Vec::new();
+        let mut patches: HashMap<usize, Expr> = HashMap::new();
+        let mut args: Vec<Expr> = Vec::new();
+        let mut condition: Option<(usize, Expr)> = None;
+
+        for (n, &(pc, ins)) in instructions.iter().enumerate() {
+            let read = |idx| self.read(function, &patches, pc, &ins, idx);
+            let last = n + 1 == instructions.len();
+
+            let value = match ins.opcode {
+                Opcode::Add => Expr::add(read(0), read(1)),
+                Opcode::Mul => Expr::mul(read(0), read(1)),
+                Opcode::LessThan => Expr::Bin(Box::new(read(0)), "<", Box::new(read(1))),
+                Opcode::Equals => Expr::Bin(Box::new(read(0)), "==", Box::new(read(1))),
+                Opcode::Input => Expr::Var("input()".to_string()),
+                Opcode::Output => {
+                    stmts.push(Stmt::Output(read(0)));
+                    continue;
+                }
+                Opcode::AdjustBase => {
+                    let prologue = pc == function.entry && function.frame != 0;
+                    let epilogue = n + 2 == instructions.len()
+                        && function.frame != 0
+                        && function.frame.checked_neg() == Some(self.word(pc, 0));
+                    if !prologue && !epilogue {
+                        stmts.push(Stmt::AdjustBase(read(0)));
+                    }
+                    continue;
+                }
+                Opcode::Halt => return (stmts, Exit::Halt),
+                Opcode::JumpIfTrue | Opcode::JumpIfFalse => {
+                    let mut cond = match condition.take() {
+                        Some((address, cond))
+                            if ins.modes[0] == ParamMode::Position
+                                && self.word(pc, 0) as usize == address =>
+                        {
+                            cond
+                        }
+                        _ => read(0),
+                    };
+                    if ins.opcode == Opcode::JumpIfFalse {
+                        cond = cond.not();
+                    }
+                    let target = read(1);
+
+                    let exit = if call {
+                        let callee = self.word(pc, 1) as usize;
+                        if args.iter().any(|a| a.mentions("out")) {
+                            // arguments depend on each other, so keep them as assignments
+                            for (offset, value) in arguments.iter().zip(args.drain(..)) {
+                                stmts.push(Stmt::Assign(
+                                    self.name(function, *offset as Intcode),
+                                    value,
+                                ));
+                            }
+                            let names = arguments
+                                .iter()
+                                .map(|&o| self.name(function, o as Intcode))
+                                .collect();
+                            Exit::Call(callee, names)
+                        } else {
+                            Exit::Call(callee, args)
+                        }
+                    } else {
+                        // after the epilogue rb+0 is the return address again
+                        let returns = function.frame != 0
+                            && ins.modes[1] == ParamMode::Relative
+                            && self.word(pc, 1) == 0;
+                        match (cond, target) {
+                            (Expr::Num(0), _) => Exit::Next,
+                            (Expr::Num(_), _) if returns => Exit::Return,
+                            (Expr::Num(_), Expr::Num(t)) => Exit::Jump(t as usize),
+                            (cond, Expr::Num(t)) => Exit::Branch(cond, t as usize),
+                            (_, target) => Exit::Indirect(target),
+                        }
+                    };
+                    return (stmts, exit);
+                }
+            };
+
+            let count = ins.opcode.params();
+            let destination = self.location(function, &patches, pc, &ins, count - 1);
+            let lhs = match (ins.modes[count - 1], destination) {
+                (ParamMode::Position, Expr::Num(address)) => {
+                    let address = address as usize;
+                    if self.patched.contains(&address) {
+                        patches.insert(address, value);
+                        continue;
+                    }
+                    if self.temps.contains(&address) && !last {
+                        condition = Some((address, value));
+                        continue;
+                    }
+                    Expr::Mem(Box::new(Expr::Num(address as Intcode)))
+                }
+                (ParamMode::Position, e) => Expr::Mem(Box::new(e)),
+                (_, e) => e,
+            };
+
+            if call && ins.modes[count - 1] == ParamMode::Relative {
+                let offset = self.word(pc, count - 1);
+                if offset == 0 && return_address(self.program, pc, &ins).is_some() {
+                    continue;
+                }
+                if offset > 0 && arguments.contains(&(offset as usize)) {
+                    args.push(value);
+                    continue;
+                }
+            }
+            stmts.push(Stmt::Assign(lhs, value));
+        }
+
+        (stmts, Exit::Next)
+    }
+
+    /// Structure blocks `lo..hi` of `function`, jumps to `ctx.next` falling out of the region
+    fn region(
+        &self,
+        function: &Function,
+        lo: usize,
+        hi: usize,
+        ctx: &Context,
+        gotos: &mut BTreeSet<usize>,
+    ) -> Vec<Stmt> {
+        let index: HashMap<usize, usize> = function
+            .blocks
+            .iter()
+            .enumerate()
+            .map(|(i, &start)| (start, i))
+            .collect();
+        let mut out = Vec::new();
+        let mut i = lo;
+
+        while i < hi {
+            let start = function.blocks[i];
+
+            // the furthest jump back to this block closes a loop
+            let back = (i..hi).rev().find(|&j| {
+                ctx.head != Some(i)
+                    && !self.is_call(function.blocks[j])
+                    && self
+                        .cfg
+                        .successors(function.blocks[j])
+                        .any(|edge| edge.to == start && edge.kind == EdgeKind::Taken)
+            });
+            if let Some(j) = back {
+                let inner = Context {
+                    head: Some(i),
+                    exit: Some(j + 1),
+                    next: j + 1,
+                };
+                let mut body = self.region(function, i, j + 1, &inner, gotos);
+                out.push(match body.pop() {
+                    Some(Stmt::If(cond, then, els))
+                        if then == [Stmt::Continue] && els.is_empty() =>
+                    {
+                        Stmt::DoWhile(body, cond)
+                    }
+                    Some(Stmt::Continue) => Stmt::Loop(body),
+                    Some(stmt) => {
+                        body.push(stmt);
+                        Stmt::Loop(body)
+                    }
+                    None => Stmt::Loop(body),
+                });
+                i = j + 1;
+                continue;
+            }
+
+            out.push(Stmt::Label(start));
+            let (stmts, exit) = self.translate(function, self.block(start));
+            out.extend(stmts);
+
+            let jump = |target: usize, gotos: &mut BTreeSet<usize>| match index.get(&target) {
+                Some(&t) if Some(t) == ctx.head => Some(Stmt::Continue),
+                Some(&t) if Some(t) == ctx.exit => Some(Stmt::Break),
+                Some(&t) if t == ctx.next => None,
+                _ => {
+                    gotos.insert(target);
+                    Some(Stmt::Goto(target))
+                }
+            };
+
+            match exit {
+                Exit::Next => (),
+                Exit::Halt => out.push(Stmt::Halt),
+                Exit::Return => out.push(Stmt::Return),
+                Exit::Indirect(target) => out.push(Stmt::GotoIndirect(target)),
+                Exit::Call(callee, args) => out.push(Stmt::Call(callee, args)),
+                Exit::Jump(target) => {
+                    if i + 1 < hi && index.get(&target) == Some(&(i + 1)) {
+                        // jumps to the next block
+                    } else {
+                        out.extend(jump(target, gotos));
+                    }
+                }
+                Exit::Branch(cond, target) => match index.get(&target) {
+                    Some(&t) if t > i + 1 && t <= hi && Some(t) != ctx.exit => {
+                        let follow = |t| if t == hi { ctx.next } else { t };
+                        let last = function.blocks[t - 1];
+                        let join = match self.translate(function, self.block(last)).1 {
+                            Exit::Jump(m) => index.get(&m).copied().filter(|&m| m > t && m <= hi),
+                            _ => None,
+                        };
+
+                        let nested = |lo, hi, next, gotos: &mut BTreeSet<usize>| {
+                            let inner = Context {
+                                head: ctx.head,
+                                exit: ctx.exit,
+                                next,
+                            };
+                            self.region(function, lo, hi, &inner, gotos)
+                        };
+                        match join {
+                            Some(m) => {
+                                let then = nested(i + 1, t, follow(m), gotos);
+                                let els = nested(t, m, follow(m), gotos);
+                                out.push(Stmt::If(cond.not(), then, els));
+                                i = m;
+                            }
+                            None => {
+                                let then = nested(i + 1, t, follow(t), gotos);
+                                out.push(Stmt::If(cond.not(), then, vec![]));
+                                i = t;
+                            }
+                        }
+                        continue;
+                    }
+                    _ => {
+                        if let Some(stmt) = jump(target, gotos) {
+                            out.push(Stmt::If(cond, vec![stmt], vec![]));
+                        }
+                    }
+                },
+            }
+            i += 1;
+        }
+
+        out
+    }
+
+    fn write_function(&self, f: &mut fmt::Formatter<'_>, function: &Function) -> fmt::Result {
+        let mut gotos = BTreeSet::new();
+        let ctx = Context {
+            head: None,
+            exit: None,
+            next: function.blocks.len(),
+        };
+        let mut body = self.region(function, 0, function.blocks.len(), &ctx, &mut gotos);
+        if function.blocks.first() != Some(&function.entry) {
+            gotos.insert(function.entry);
+            body.insert(0, Stmt::Goto(function.entry));
+        }
+
+        if function.entry == 0 {
+            writeln!(f, "void main() {{")?;
+        } else {
+            let params: Vec<_> = (1..=function.params).map(|p| format!("arg{}", p)).collect();
+            writeln!(f, "void f{:04}({}) {{", function.entry, params.join(", "))?;
+        }
+        write_stmts(f, &body, 1, &gotos)?;
+        writeln!(f, "}}")
+    }
+}
+
+fn write_stmts(
+    f: &mut fmt::Formatter<'_>,
+    stmts: &[Stmt],
+    depth: usize,
+    gotos: &BTreeSet<usize>,
+) -> fmt::Result {
+    let indent = "    ".repeat(depth);
+    for stmt in stmts {
+        match stmt {
+            Stmt::Label(address) => {
+                if gotos.contains(address) {
+                    writeln!(f, "{}L{:04}:", "    ".repeat(depth - 1), address)?;
+                }
+            }
+            Stmt::Assign(lhs, value) => writeln!(f, "{}{} = {};", indent, lhs, value)?,
+            Stmt::Output(value) => writeln!(f, "{}output({});", indent, value)?,
+            Stmt::Call(callee, args) => {
+                let args: Vec<_> = args.iter().map(|a| a.to_string()).collect();
+                writeln!(f, "{}f{:04}({});", indent, callee, args.join(", "))?
+            }
+            Stmt::AdjustBase(by) => writeln!(f, "{}rb += {};", indent, by)?,
+            Stmt::If(cond, then, els) => {
+                writeln!(f, "{}if ({}) {{", indent, cond)?;
+                write_stmts(f, then, depth + 1, gotos)?;
+                if !els.is_empty() {
+                    writeln!(f, "{}}} else {{", indent)?;
+                    write_stmts(f, els, depth + 1, gotos)?;
+                }
+                writeln!(f, "{}}}", indent)?;
+            }
+            Stmt::DoWhile(body, cond) => {
+                writeln!(f, "{}do {{", indent)?;
+                write_stmts(f, body, depth + 1, gotos)?;
+                writeln!(f, "{}}} while ({});", indent, cond)?;
+            }
+            Stmt::Loop(body) => {
+                writeln!(f, "{}while (1) {{", indent)?;
+                write_stmts(f, body, depth + 1, gotos)?;
+                writeln!(f, "{}}}", indent)?;
+            }
+            Stmt::Break => writeln!(f, "{}break;", indent)?,
+            Stmt::Continue => writeln!(f, "{}continue;", indent)?,
+            Stmt::Goto(target) => writeln!(f, "{}goto L{:04};", indent, target)?,
+            Stmt::GotoIndirect(target) => writeln!(f, "{}goto *{};", indent, target)?,
+            Stmt::Return => writeln!(f, "{}return;", indent)?,
+            Stmt::Halt => writeln!(f, "{}halt();", indent)?,
+        }
+    }
+    Ok(())
+}
+
+/// Every function as pseudocode, `main` first
+impl fmt::Display for Decompiler<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (idx, function) in self.functions.values().enumerate() {
+            if idx > 0 {
+                writeln!(f)?;
+            }
+            self.write_function(f, function)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::asm::assemble;
+
+    #[test]
+    fn functions() {
+        // prints the sum 1 + 2 + ... + n for n read from input, until n is 0
+        let program = assemble(
+            "
+                    arb  #100
+            read:   in   [n]
+                    jf   [n], #done
+                    add  [n], #0, rb+1
+                    add  #back, #0, rb+0
+                    jt   #1, #sum
+            back:   out  rb+1
+                    jt   #1, #read
+            done:   hlt
+            sum:    arb  #3
+                    add  #0, #0, rb-1
+            loop:   add  rb-1, rb-2, rb-1
+                    add  rb-2, #-1, rb-2
+                    lt   #0, rb-2, [t]
+                    jt   [t], #loop
+                    add  rb-1, #0, rb-2
+                    arb  #-3
+                    jt   #1, rb+0
+            n:      data 0
+            t:      data 0
+            ",
+        )
+        .unwrap();
+
+        let decompiler = Decompiler::new(&program);
+        assert_eq!(
+            decompiler.functions().map(|f| f.params).collect::<Vec<_>>(),
+            vec![0, 1]
+        );
+        assert_eq!(
+            decompiler.to_string(),
+            "void main() {
+    rb += 100;
+    while (1) {
+        mem[54] = input();
+        if (!mem[54]) {
+            break;
+        }
+        f0024(mem[54]);
+        output(out1);
+    }
+    halt();
+}
+
+void f0024(arg1) {
+    local1 = 0;
+    do {
+        local1 = local1 + arg1;
+        arg1 = arg1 - 1;
+    } while (0 < arg1);
+    arg1 = local1;
+    return;
+}
+"
+        );
+    }
+
+    #[test]
+    fn no_code() {
+        assert_eq!(Decompiler::new(&[]).functions().count(), 0);
+        assert_eq!(Decompiler::new(&[0, 1, 2]).to_string(), "");
+    }
+
+    #[test]
+    fn overflow() {
+        let program = assemble(
+            "
+                    add  #9223372036854775807, #1, [x]
+                    mul  #-9223372036854775808, #2, [x]
+                    add  #-9223372036854775808, #-1, [x]
+                    add  #-1, #-9223372036854775808, [x]
+                    hlt
+            x:      data 0
+            ",
+        )
+        .unwrap();
+        // constants that would overflow are left unfolded
+        assert_eq!(
+            Decompiler::new(&program).to_string(),
+            "void main() {
+    mem[17] = 9223372036854775807 + 1;
+    mem[17] = -9223372036854775808 * 2;
+    mem[17] = -9223372036854775808 - 1;
+    mem[17] = -1 + -9223372036854775808;
+    halt();
+}
+"
+        );
+    }
+
+    #[test]
+    fn arcade() {
+        let path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../aoc_13/input.txt");
+        let program = crate::read_input(&mut std::fs::File::open(path).unwrap()).unwrap();
+        let text = Decompiler::new(&program).to_string();
+
+        // the tile store indexes the grid through a patched operand
+        assert!(
+            text.contains("mem[arg1 + arg2 * 42 + 639] = arg3;"),
+            "{}",
+            text
+        );
+        assert!(text.contains("void f0578(arg1, arg2) {"));
+        assert!(text.contains("do {"));
+    }
+}
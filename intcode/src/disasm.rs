@@ -0,0 +1,359 @@
+use crate::{Instruction, Intcode, Memory, Opcode, ParamMode};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+
+const DATA_PER_LINE: usize = 8;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Number(Intcode),
+    Label(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Operand {
+    pub mode: ParamMode,
+    pub value: Value,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Line {
+    Instruction {
+        address: usize,
+        opcode: Opcode,
+        operands: Vec<Operand>,
+    },
+    Data {
+        address: usize,
+        values: Vec<Intcode>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Listing {
+    pub lines: Vec<Line>,
+    pub labels: BTreeMap<usize, String>,
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Number(n) => write!(f, "{}", n),
+            Value::Label(l) => write!(f, "{}", l),
+        }
+    }
+}
+
+impl fmt::Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.mode, &self.value) {
+            (ParamMode::Position, v) => write!(f, "[{}]", v),
+            (ParamMode::Immediate, v) => write!(f, "#{}", v),
+            (ParamMode::Relative, Value::Number(n)) if *n < 0 => write!(f, "rb{}", n),
+            (ParamMode::Relative, v) => write!(f, "rb+{}", v),
+        }
+    }
+}
+
+impl Line {
+    pub fn address(&self) -> usize {
+        match self {
+            Line::Instruction { address, .. } | Line::Data { address, .. } => *address,
+        }
+    }
+
+    /// Number of words the line occupies
+    pub fn size(&self) -> usize {
+        match self {
+            Line::Instruction { operands, .. } => operands.len() + 1,
+            Line::Data { values, .. } => values.len(),
+        }
+    }
+}
+
+impl fmt::Display for Line {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Line::Instruction {
+                opcode, operands, ..
+            } => {
+                let operands: Vec<_> = operands.iter().map(|o| o.to_string()).collect();
+                write!(f, "{:<5}{}", opcode.mnemonic(), operands.join(", "))
+            }
+            Line::Data { values, .. } => {
+                let values: Vec<_> = values.iter().map(|v| v.to_string()).collect();
+                write!(f, "{:<5}{}", "DATA", values.join(", "))
+            }
+        }
+    }
+}
+
+impl fmt::Display for Listing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for line in &self.lines {
+            let label = self
+                .labels
+                .get(&line.address())
+                .map(|l| format!("{}:", l))
+                .unwrap_or_default();
+
+            let body = line.to_string();
+            writeln!(f, "{:>5}  {:<8}{}", line.address(), label, body.trim_end())?;
+        }
+
+        Ok(())
+    }
+}
+
+fn operand_value(program: &[Intcode], pc: usize, idx: usize) -> Intcode {
+    program[pc + 1 + idx]
+}
+
+/// Recognise a call saving its return address, e.g. `ADD #ret, #0, rb+0` followed by
+/// `JT #1, #func`, where `ret` is the address right after the jump
+pub(crate) fn return_address(program: &[Intcode], pc: usize, ins: &Instruction) -> Option<Intcode> {
+    let [a, b, c] = ins.modes;
+    if a != ParamMode::Immediate
+        || b != ParamMode::Immediate
+        || c != ParamMode::Relative
+        || operand_value(program, pc, 2) != 0
+    {
+        return None;
+    }
+
+    let next = pc + ins.size();
+    match program.get(next).map(|&w| Instruction::decode(next, w)) {
+        Some(Ok(jump)) if matches!(jump.opcode, Opcode::JumpIfTrue | Opcode::JumpIfFalse) => (),
+        _ => return None,
+    }
+
+    let ret = (next + 3) as Intcode;
+    let (x, y) = (operand_value(program, pc, 0), operand_value(program, pc, 1));
+    match ins.opcode {
+        Opcode::Add if (x, y) == (ret, 0) || (x, y) == (0, ret) => Some(ret),
+        Opcode::Mul if (x, y) == (ret, 1) || (x, y) == (1, ret) => Some(ret),
+        _ => None,
+    }
+}
+
+/// Walk the program from address 0 following fallthrough and immediate jump targets. Returns
+/// the instruction start addresses and the operand slots that reference code.
+pub(crate) fn reachable(program: &[Intcode]) -> (BTreeMap<usize, Instruction>, HashSet<usize>) {
+    reachable_from(program, &[0])
+}
+
+/// Like `reachable`, walking from each of `entries` instead of just address 0
+fn reachable_from(
+    program: &[Intcode],
+    entries: &[usize],
+) -> (BTreeMap<usize, Instruction>, HashSet<usize>) {
+    let mut starts = BTreeMap::new();
+    let mut covered = vec![false; program.len()];
+    let mut references = HashSet::new();
+    let mut work: Vec<_> = entries.iter().rev().copied().collect();
+
+    while let Some(mut pc) = work.pop() {
+        while pc < program.len() && !covered[pc] {
+            // words with stray mode digits would not survive reassembly, so treat them as data
+            let ins = match Instruction::decode(pc, program[pc]) {
+                Ok(ins) if pc + ins.size() <= program.len() && ins.encode() == program[pc] => ins,
+                _ => break,
+            };
+            if covered[pc..pc + ins.size()].iter().any(|&c| c) {
+                break;
+            }
+
+            covered[pc..pc + ins.size()]
+                .iter_mut()
+                .for_each(|c| *c = true);
+            starts.insert(pc, ins);
+
+            match ins.opcode {
+                Opcode::Halt => break,
+                Opcode::JumpIfTrue | Opcode::JumpIfFalse => {
+                    if ins.modes[1] == ParamMode::Immediate {
+                        let target = operand_value(program, pc, 1);
+                        if target >= 0 {
+                            references.insert(pc + 2);
+                            work.push(target as usize);
+                        }
+                    }
+
+                    if ins.modes[0] == ParamMode::Immediate {
+                        let cond = operand_value(program, pc, 0) != 0;
+                        if cond == (ins.opcode == Opcode::JumpIfTrue) {
+                            // unconditional jump, nothing falls through
+                            break;
+                        }
+                    }
+                }
+                _ => {
+                    if let Some(ret) = return_address(program, pc, &ins) {
+                        let slot = if operand_value(program, pc, 0) == ret {
+                            pc + 1
+                        } else {
+                            pc + 2
+                        };
+                        references.insert(slot);
+                        work.push(ret as usize);
+                    }
+                }
+            }
+
+            pc += ins.size();
+        }
+    }
+
+    (starts, references)
+}
+
+/// Decode whatever is at `address` without any analysis, falling back to a single data word
+pub fn disassemble_at<M: Memory + ?Sized>(memory: &M, address: usize) -> Line {
+    let word = |a: usize| memory.read(a);
+
+    match Instruction::decode(address, word(address)) {
+        Ok(ins) => Line::Instruction {
+            address,
+            opcode: ins.opcode,
+            operands: (0..ins.opcode.params())
+                .map(|idx| Operand {
+                    mode: ins.modes[idx],
+                    value: Value::Number(word(address + 1 + idx)),
+                })
+                .collect(),
+        },
+        Err(_) => Line::Data {
+            address,
+            values: vec![word(address)],
+        },
+    }
+}
+
+pub fn disassemble(program: &[Intcode]) -> Listing {
+    disassemble_from(program, &[0])
+}
+
+/// Disassemble code reachable from any of `entries`, such as jump targets only known at run
+/// time
+pub fn disassemble_from(program: &[Intcode], entries: &[usize]) -> Listing {
+    let (starts, references) = reachable_from(program, entries);
+
+    let labels: BTreeMap<_, _> = references
+        .iter()
+        .map(|&slot| program[slot] as usize)
+        .filter(|target| starts.contains_key(target))
+        .map(|target| (target, format!("L{:04}", target)))
+        .collect();
+
+    let mut lines = Vec::new();
+    let mut pc = 0;
+    while pc < program.len() {
+        if let Some(ins) = starts.get(&pc) {
+            let operands = (0..ins.opcode.params())
+                .map(|idx| {
+                    let slot = pc + 1 + idx;
+                    let value = match labels.get(&(program[slot] as usize)) {
+                        Some(label) if references.contains(&slot) => Value::Label(label.clone()),
+                        _ => Value::Number(program[slot]),
+                    };
+
+                    Operand {
+                        mode: ins.modes[idx],
+                        value,
+                    }
+                })
+                .collect();
+
+            lines.push(Line::Instruction {
+                address: pc,
+                opcode: ins.opcode,
+                operands,
+            });
+            pc += ins.size();
+        } else {
+            let end = (pc..program.len())
+                .take(DATA_PER_LINE)
+                .find(|a| starts.contains_key(a))
+                .unwrap_or_else(|| program.len().min(pc + DATA_PER_LINE));
+
+            lines.push(Line::Data {
+                address: pc,
+                values: program[pc..end].to_vec(),
+            });
+            pc = end;
+        }
+    }
+
+    Listing { lines, labels }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn listing() {
+        let program = [
+            3, 21, 1008, 21, 8, 20, 1005, 20, 22, 107, 8, 21, 20, 1006, 20, 31, 1106, 0, 36, 98, 0,
+            0, 1002, 21, 125, 20, 4, 20, 1105, 1, 46, 104, 999, 1105, 1, 46, 1101, 1000, 1, 20, 4,
+            20, 1105, 1, 46, 98, 99,
+        ];
+
+        let expected = "    0          IN   [21]
+    2          EQ   [21], #8, [20]
+    6          JT   [20], #L0022
+    9          LT   #8, [21], [20]
+   13          JF   [20], #L0031
+   16          JF   #0, #L0036
+   19          DATA 98, 0, 0
+   22  L0022:  MUL  [21], #125, [20]
+   26          OUT  [20]
+   28          JT   #1, #L0046
+   31  L0031:  OUT  #999
+   33          JT   #1, #L0046
+   36  L0036:  ADD  #1000, #1, [20]
+   40          OUT  [20]
+   42          JT   #1, #L0046
+   45          DATA 98
+   46  L0046:  HLT
+";
+        assert_eq!(disassemble(&program).to_string(), expected);
+    }
+
+    #[test]
+    fn return_address() {
+        let program = [21101, 7, 0, 0, 1105, 1, 8, 99, 2106, 0, 0];
+        let listing = disassemble(&program);
+
+        assert_eq!(
+            listing.lines[0],
+            Line::Instruction {
+                address: 0,
+                opcode: Opcode::Add,
+                operands: vec![
+                    Operand {
+                        mode: ParamMode::Immediate,
+                        value: Value::Label("L0007".into()),
+                    },
+                    Operand {
+                        mode: ParamMode::Immediate,
+                        value: Value::Number(0),
+                    },
+                    Operand {
+                        mode: ParamMode::Relative,
+                        value: Value::Number(0),
+                    },
+                ],
+            }
+        );
+        assert_eq!(listing.labels.get(&7), Some(&"L0007".to_string()));
+        assert_eq!(
+            listing.to_string(),
+            "    0          ADD  #L0007, #0, rb+0
+    4          JT   #1, #L0008
+    7  L0007:  HLT
+    8  L0008:  JF   #0, rb+0
+"
+        );
+    }
+}
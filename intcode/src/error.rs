@@ -0,0 +1,39 @@
+use crate::Intcode;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum IntcodeError {
+    UnknownOpcode { pc: usize, opcode: Intcode },
+    InvalidMode { pc: usize, mode: Intcode },
+    ImmediateWrite { pc: usize },
+    NegativeAddress { pc: usize, address: Intcode },
+    Overflow { pc: usize },
+    SelfModifying { pc: usize, address: usize },
+    OutputUnderflow,
+}
+
+impl fmt::Display for IntcodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IntcodeError::UnknownOpcode { pc, opcode } => {
+                write!(f, "opcode unknown at {}: {}", pc, opcode)
+            }
+            IntcodeError::InvalidMode { pc, mode } => {
+                write!(f, "unknown mode at {}: {}", pc, mode)
+            }
+            IntcodeError::ImmediateWrite { pc } => {
+                write!(f, "write in immediate mode at {}", pc)
+            }
+            IntcodeError::NegativeAddress { pc, address } => {
+                write!(f, "negative address at {}: {}", pc, address)
+            }
+            IntcodeError::Overflow { pc } => write!(f, "overflow at {}", pc),
+            IntcodeError::SelfModifying { pc, address } => {
+                write!(f, "code modified at {}: [{}]", pc, address)
+            }
+            IntcodeError::OutputUnderflow => write!(f, "no output available"),
+        }
+    }
+}
+
+impl std::error::Error for IntcodeError {}
@@ -0,0 +1,477 @@
+use crate::blocks::BlockComputer;
+use crate::budget::Budget;
+use crate::{
+    Computer, ComputerStatus, Intcode, IntcodeError, Memory, Opcode, PagedMemory, ParamMode, Word,
+};
+use num::BigInt;
+use std::cell::Cell;
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::panic::{self, UnwindSafe};
+use std::sync::Once;
+
+/// Instructions any engine may run on a case before it counts as not halting
+pub const BUDGET: usize = 10_000;
+
+/// Cases touching memory past this are skipped, as dense memory would have to grow to it
+pub const MEMORY: usize = 1 << 12;
+
+/// Small xorshift generator, so a failing seed reproduces everywhere
+#[derive(Debug, Clone)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // xorshift gets stuck on 0
+        Self(seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    pub fn range(&mut self, range: &RangeInclusive<Intcode>) -> Intcode {
+        let span = (range.end() - range.start()) as usize + 1;
+        range.start() + self.below(span) as Intcode
+    }
+
+    fn pick<T: Copy>(&mut self, items: &[T]) -> T {
+        items[self.below(items.len())]
+    }
+}
+
+/// What the generated programs may contain, to suit engines that only know part of Intcode
+#[derive(Debug, Clone)]
+pub struct Profile {
+    pub opcodes: Vec<Opcode>,
+    pub modes: Vec<ParamMode>,
+    pub instructions: usize,
+    pub values: RangeInclusive<Intcode>,
+}
+
+impl Profile {
+    /// Everything, including relative mode
+    pub fn full() -> Self {
+        Self {
+            opcodes: vec![
+                Opcode::Add,
+                Opcode::Mul,
+                Opcode::Input,
+                Opcode::Output,
+                Opcode::JumpIfTrue,
+                Opcode::JumpIfFalse,
+                Opcode::LessThan,
+                Opcode::Equals,
+                Opcode::AdjustBase,
+            ],
+            modes: vec![
+                ParamMode::Position,
+                ParamMode::Immediate,
+                ParamMode::Relative,
+            ],
+            instructions: 12,
+            values: -5..=20,
+        }
+    }
+
+    /// Add and multiply on non-negative values in position mode, as on day 2
+    pub fn day2() -> Self {
+        Self {
+            opcodes: vec![Opcode::Add, Opcode::Mul],
+            modes: vec![ParamMode::Position],
+            instructions: 8,
+            values: 0..=20,
+        }
+    }
+
+    /// Opcodes 1 to 8 in position and immediate mode, as on day 5
+    pub fn day5() -> Self {
+        Self {
+            opcodes: Self::full().opcodes[..8].to_vec(),
+            modes: vec![ParamMode::Position, ParamMode::Immediate],
+            ..Self::full()
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Case {
+    pub program: Vec<Intcode>,
+    pub inputs: Vec<Intcode>,
+}
+
+impl fmt::Display for Case {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let join = |values: &[Intcode]| {
+            let values: Vec<_> = values.iter().map(|v| v.to_string()).collect();
+            values.join(",")
+        };
+        writeln!(f, "program: {}", join(&self.program))?;
+        writeln!(f, "inputs:  {}", join(&self.inputs))
+    }
+}
+
+/// A random program of `profile.instructions` instructions, a halt and a few data cells.
+/// Addresses stay inside the program and jumps land on instructions, though the program is
+/// free to rewrite any of that as it runs.
+pub fn generate(rng: &mut Rng, profile: &Profile) -> Case {
+    const DATA: usize = 8;
+
+    let opcodes: Vec<_> = (0..profile.instructions)
+        .map(|_| rng.pick(&profile.opcodes))
+        .collect();
+    let mut starts = Vec::new();
+    let mut size = 0;
+    for opcode in &opcodes {
+        starts.push(size);
+        size += opcode.params() + 1;
+    }
+    starts.push(size);
+    let total = size + 1 + DATA;
+
+    let mut program = Vec::with_capacity(total);
+    for opcode in opcodes {
+        let count = opcode.params();
+        let mut modes = [ParamMode::Position; 3];
+        let mut params = Vec::new();
+
+        for (idx, mode) in modes.iter_mut().enumerate().take(count) {
+            let jump_target =
+                matches!(opcode, Opcode::JumpIfTrue | Opcode::JumpIfFalse) && idx == 1;
+            let written = opcode.writes() && idx == count - 1;
+
+            *mode = if jump_target && profile.modes.contains(&ParamMode::Immediate) {
+                ParamMode::Immediate
+            } else {
+                loop {
+                    let mode = rng.pick(&profile.modes);
+                    if !(written && mode == ParamMode::Immediate) {
+                        break mode;
+                    }
+                }
+            };
+
+            params.push(match *mode {
+                ParamMode::Immediate if jump_target => rng.pick(&starts) as Intcode,
+                ParamMode::Position => rng.below(total) as Intcode,
+                _ => rng.range(&profile.values),
+            });
+        }
+
+        let modes = modes
+            .iter()
+            .rev()
+            .fold(0, |word, mode| word * 10 + mode.code());
+        program.push(modes * 100 + opcode.code());
+        program.extend(params);
+    }
+    program.push(Opcode::Halt.code());
+    program.extend((0..DATA).map(|_| rng.range(&profile.values)));
+
+    let inputs = (0..4).map(|_| rng.range(&profile.values)).collect();
+    Case { program, inputs }
+}
+
+/// What an engine made of a case
+#[derive(Debug, Clone, PartialEq)]
+pub struct Run {
+    pub outputs: Vec<Intcode>,
+    pub halted: bool,
+    /// Final memory without trailing zeros
+    pub memory: Vec<Intcode>,
+}
+
+impl Run {
+    fn new(outputs: Vec<Intcode>, halted: bool, mut memory: Vec<Intcode>) -> Self {
+        while memory.last() == Some(&0) {
+            memory.pop();
+        }
+        Self {
+            outputs,
+            halted,
+            memory,
+        }
+    }
+}
+
+/// Runs a case, or returns `None` when the case says nothing about it, such as arithmetic
+/// overflowing the engine's words
+pub type RunFn<'a> = Box<dyn Fn(&Case) -> Option<Run> + 'a>;
+
+pub struct Engine<'a> {
+    pub name: &'a str,
+    pub run: RunFn<'a>,
+}
+
+impl<'a> Engine<'a> {
+    pub fn new<F: Fn(&Case) -> Option<Run> + 'a>(name: &'a str, run: F) -> Self {
+        Self {
+            name,
+            run: Box::new(run),
+        }
+    }
+}
+
+fn run_computer<W: Word, M: Memory<W>>(mut c: Computer<W, M>, case: &Case) -> Option<Run> {
+    c.set_checked(true);
+    for &input in &case.inputs {
+        c.input_add(W::from_i64(input)?);
+    }
+
+    let mut budget = Budget {
+        steps: Some(BUDGET),
+        ..Budget::default()
+    };
+    loop {
+        match c.try_run_budget(&mut budget) {
+            Ok(ComputerStatus::ReturnedValue) => (),
+            Ok(_) => break,
+            Err(IntcodeError::Overflow { .. }) => return None,
+            Err(_) => break,
+        }
+    }
+
+    let outputs = c.output.iter().map(|v| v.to_i64()).collect::<Option<_>>()?;
+    let mut memory = Vec::new();
+    for (start, words) in c.memory().regions() {
+        if start + words.len() > MEMORY {
+            return None;
+        }
+        memory.resize(start, 0);
+        for word in words {
+            memory.push(word.to_i64()?);
+        }
+    }
+    Some(Run::new(outputs, c.halted, memory))
+}
+
+fn run_blocks(case: &Case) -> Option<Run> {
+    let mut c = BlockComputer::new(&case.program);
+    c.set_checked(true);
+    c.input_add_all(&case.inputs);
+
+    for _ in 0..BUDGET {
+        match c.step() {
+            Ok(None) | Ok(Some(ComputerStatus::ReturnedValue)) => (),
+            Ok(Some(_)) => break,
+            Err(IntcodeError::Overflow { .. }) => return None,
+            Err(_) => break,
+        }
+    }
+
+    Some(Run::new(
+        c.output.iter().copied().collect(),
+        c.halted,
+        c.memory().clone(),
+    ))
+}
+
+/// The reference: an `i64` `Computer` on paged memory, which copes with far away writes
+pub fn reference(case: &Case) -> Option<Run> {
+    let memory = PagedMemory::from(&case.program[..]);
+    run_computer(Computer::with_memory(memory), case)
+}
+
+thread_local! {
+    static QUIET: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Run an engine that panics on bad programs, with `None` for a panic and without printing it.
+/// Panics on other threads, such as other failing tests, are still printed.
+pub fn quietly<T>(run: impl FnOnce() -> T + UnwindSafe) -> Option<T> {
+    static HOOK: Once = Once::new();
+    HOOK.call_once(|| {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if !QUIET.with(Cell::get) {
+                previous(info);
+            }
+        }));
+    });
+
+    QUIET.with(|quiet| quiet.set(true));
+    let result = panic::catch_unwind(run);
+    QUIET.with(|quiet| quiet.set(false));
+    result.ok()
+}
+
+/// Every engine in this crate, reference first
+pub fn engines() -> Vec<Engine<'static>> {
+    vec![
+        Engine::new("paged", reference),
+        Engine::new("i64", |case| {
+            run_computer(Computer::new(&case.program), case)
+        }),
+        Engine::new("i128", |case| {
+            let program = case.program.iter().map(|&w| w.into()).collect();
+            run_computer(Computer::<i128>::with_memory(program), case)
+        }),
+        Engine::new("BigInt", |case| {
+            let program = case.program.iter().map(|&w| w.into()).collect();
+            run_computer(Computer::<BigInt>::with_memory(program), case)
+        }),
+        Engine::new("blocks", run_blocks),
+    ]
+}
+
+#[derive(Debug, Clone)]
+pub struct Divergence {
+    pub case: Case,
+    pub runs: Vec<(String, Option<Run>)>,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.case)?;
+        for (name, run) in &self.runs {
+            match run {
+                Some(run) => writeln!(
+                    f,
+                    "{:<8}halted: {}, outputs: {:?}, memory: {:?}",
+                    name, run.halted, run.outputs, run.memory
+                )?,
+                None => writeln!(f, "{:<8}skipped", name)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Run `case` on every engine, stopping at the first one to skip it. The first engine
+/// therefore screens cases for the rest, which may not cope with endless loops. Returns
+/// whether every engine ran the case.
+fn compare(case: &Case, engines: &[Engine<'_>]) -> Result<bool, Divergence> {
+    let mut runs: Vec<(String, Option<Run>)> = Vec::new();
+
+    for engine in engines {
+        let run = (engine.run)(case);
+        let skipped = run.is_none();
+        runs.push((engine.name.to_string(), run));
+        if skipped {
+            break;
+        }
+    }
+
+    let compared: Vec<_> = runs.iter().filter_map(|(_, run)| run.as_ref()).collect();
+    if compared.windows(2).all(|pair| pair[0] == pair[1]) {
+        Ok(compared.len() == engines.len())
+    } else {
+        Err(Divergence {
+            case: case.clone(),
+            runs,
+        })
+    }
+}
+
+/// Greedily simplify a diverging case while it keeps diverging
+pub fn minimise(divergence: Divergence, engines: &[Engine<'_>]) -> Divergence {
+    let mut best = divergence;
+
+    loop {
+        let case = &best.case;
+        let mut candidates = Vec::new();
+
+        for idx in 0..case.inputs.len() {
+            let mut smaller = case.clone();
+            smaller.inputs.remove(idx);
+            candidates.push(smaller);
+        }
+        if !case.program.is_empty() {
+            let mut smaller = case.clone();
+            smaller.program.pop();
+            candidates.push(smaller);
+        }
+        for (idx, &word) in case.program.iter().enumerate() {
+            for simpler in [0, 1, 99, word % 100, word / 2].iter() {
+                if simpler.unsigned_abs() < word.unsigned_abs() {
+                    let mut smaller = case.clone();
+                    smaller.program[idx] = *simpler;
+                    candidates.push(smaller);
+                }
+            }
+        }
+
+        match candidates
+            .iter()
+            .find_map(|candidate| compare(candidate, engines).err())
+        {
+            Some(smaller) => best = smaller,
+            None => return best,
+        }
+    }
+}
+
+/// Compare `engines` on `cases` random programs, returning how many every engine ran, or the
+/// first divergence found, minimised
+pub fn fuzz(
+    seed: u64,
+    cases: usize,
+    profile: &Profile,
+    engines: &[Engine<'_>],
+) -> Result<usize, Divergence> {
+    let mut rng = Rng::new(seed);
+    let mut compared = 0;
+
+    for _ in 0..cases {
+        let case = generate(&mut rng, profile);
+        match compare(&case, engines) {
+            Ok(all) => compared += all as usize,
+            Err(divergence) => return Err(minimise(divergence, engines)),
+        }
+    }
+
+    Ok(compared)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn engines_agree() {
+        let compared =
+            fuzz(1, 300, &Profile::full(), &engines()).unwrap_or_else(|d| panic!("{}", d));
+        assert!(compared > 200, "only {} cases compared", compared);
+    }
+
+    #[test]
+    fn quiet() {
+        assert_eq!(quietly(|| 1), Some(1));
+        assert_eq!(quietly(|| -> i32 { panic!("bad program") }), None);
+        assert!(!QUIET.with(Cell::get));
+    }
+
+    #[test]
+    fn minimising() {
+        // an engine that loses the last output
+        let broken = |case: &Case| {
+            let mut run = reference(case)?;
+            run.outputs.pop();
+            Some(run)
+        };
+        let engines = [
+            Engine::new("paged", reference),
+            Engine::new("broken", broken),
+        ];
+
+        let divergence = fuzz(1, 100, &Profile::full(), &engines).unwrap_err();
+        assert_eq!(
+            divergence.case,
+            Case {
+                program: vec![4],
+                inputs: vec![]
+            }
+        );
+        assert_eq!(
+            divergence.to_string().lines().nth(2),
+            Some("paged   halted: false, outputs: [4], memory: [4]")
+        );
+    }
+}
@@ -0,0 +1,231 @@
+use crate::{Computer, ComputerStatus, IntcodeError, Memory, Word};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemoryWrite<W> {
+    pub address: usize,
+    pub old: W,
+    pub value: W,
+}
+
+/// One executed instruction, with the state it started from and what it changed
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record<W> {
+    pub pc: usize,
+    pub base_offset: isize,
+    pub write: Option<MemoryWrite<W>>,
+    pub input: Option<W>,
+    pub output: bool,
+    /// `Memory::extent` before the instruction, so growth can be undone
+    extent: usize,
+}
+
+/// An undo log of every instruction executed since recording started
+#[derive(Debug, Clone)]
+pub struct History<W> {
+    records: Vec<Record<W>>,
+    pending: Option<Record<W>>,
+}
+
+impl<W> History<W> {
+    pub(crate) fn new() -> Self {
+        Self {
+            records: Vec::new(),
+            pending: None,
+        }
+    }
+
+    /// Number of instructions recorded
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    pub fn records(&self) -> &[Record<W>] {
+        &self.records
+    }
+
+    /// Index of the last recorded instruction that wrote to `address`
+    pub fn last_write(&self, address: usize) -> Option<usize> {
+        self.records
+            .iter()
+            .rposition(|record| matches!(&record.write, Some(w) if w.address == address))
+    }
+
+    pub(crate) fn begin(&mut self, pc: usize, base_offset: isize, extent: usize) {
+        self.pending = Some(Record {
+            pc,
+            base_offset,
+            write: None,
+            input: None,
+            output: false,
+            extent,
+        });
+    }
+
+    pub(crate) fn write(&mut self, address: usize, old: W, value: W) {
+        if let Some(record) = &mut self.pending {
+            record.write = Some(MemoryWrite {
+                address,
+                old,
+                value,
+            });
+        }
+    }
+
+    pub(crate) fn input(&mut self, value: W) {
+        if let Some(record) = &mut self.pending {
+            record.input = Some(value);
+        }
+    }
+
+    pub(crate) fn output(&mut self) {
+        if let Some(record) = &mut self.pending {
+            record.output = true;
+        }
+    }
+
+    /// Keep the instruction begun last, or drop it when it faulted
+    pub(crate) fn finish(&mut self, ok: bool) {
+        if let Some(record) = self.pending.take().filter(|_| ok) {
+            self.records.push(record);
+        }
+    }
+}
+
+impl<W: Word, M: Memory<W>> Computer<W, M> {
+    /// Start recording every instruction so they can be undone with `step_back`
+    pub fn record_history(&mut self) {
+        self.history = Some(Box::new(History::new()));
+    }
+
+    pub fn history(&self) -> Option<&History<W>> {
+        self.history.as_deref()
+    }
+
+    /// Undo the last recorded instruction. Input it read goes back on the front of the input
+    /// queue, output it produced is taken off the back of the output queue if still there.
+    pub fn step_back(&mut self) -> bool {
+        let record = match self.history.as_mut().and_then(|h| h.records.pop()) {
+            Some(record) => record,
+            None => return false,
+        };
+
+        if let Some(write) = record.write {
+            self.memory.write(write.address, write.old);
+        }
+        self.memory.truncate(record.extent);
+        if let Some(input) = record.input {
+            self.input.push_front(input);
+        }
+        if record.output {
+            self.output.pop_back();
+        }
+        self.program_counter.set(record.pc);
+        self.current_instruction = record.pc;
+        self.base_offset = record.base_offset;
+        // only the instruction that halted is recorded, never one run after it
+        self.halted = false;
+        true
+    }
+
+    /// Go to the point where `count` instructions have been recorded, stepping back or running
+    /// forward. Running forward stops early on a halt or when input runs out.
+    pub fn seek(&mut self, count: usize) -> Result<Option<ComputerStatus>, IntcodeError> {
+        loop {
+            let len = self.history().map_or(0, History::len);
+            if len > count {
+                self.step_back();
+            } else if len < count && self.history.is_some() {
+                match self.step()? {
+                    Some(ComputerStatus::ReturnedValue) | None => (),
+                    status => return Ok(status),
+                }
+            } else {
+                return Ok(None);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::asm::assemble;
+    use crate::{Computer, ComputerStatus, Intcode};
+
+    fn counter() -> Vec<Intcode> {
+        assemble(
+            "
+                    arb  #1
+                    in   [limit]
+            loop:   add  [count], #1, [count]
+                    out  [count]
+                    lt   [count], [limit], [tmp]
+                    jt   [tmp], #loop
+                    hlt
+            limit:  data 0
+            count:  data 0
+            tmp:    data 0
+            ",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn rewind() {
+        let program = counter();
+        let mut c = Computer::new(&program);
+        c.record_history();
+        c.input_add(3);
+        while c.run() != ComputerStatus::Halt {}
+
+        let end = c.clone();
+        let history = c.history().unwrap();
+        assert_eq!(history.len(), 15);
+        let count = program.len() - 2;
+        assert_eq!(history.last_write(count), Some(10));
+        assert_eq!(history.records()[10].pc, 4);
+
+        // back to just before the last increment
+        c.seek(10).unwrap();
+        assert_eq!(c.memory()[count], 2);
+        assert_eq!(c.program_counter(), 4);
+        assert_eq!(c.output, vec![1, 2]);
+        assert!(!c.halted);
+
+        assert_eq!(c.seek(0).unwrap(), None);
+        assert_eq!(c.memory(), &program);
+        assert_eq!(c.base_offset(), 0);
+        assert_eq!(c.input(), &vec![3]);
+        assert!(c.output.is_empty());
+        assert!(!c.step_back());
+
+        // replaying lands in the same place
+        assert_eq!(c.seek(100).unwrap(), Some(ComputerStatus::Halt));
+        assert_eq!(c.memory(), end.memory());
+        assert_eq!(c.output, end.output);
+        assert_eq!(c.program_counter(), end.program_counter());
+        assert!(c.halted);
+
+        // running on after the halt records nothing
+        assert_eq!(c.run(), ComputerStatus::Halt);
+        assert_eq!(c.history().unwrap().len(), 15);
+        assert!(c.step_back());
+        assert!(!c.halted);
+        assert_eq!(c.program_counter(), end.program_counter());
+    }
+
+    #[test]
+    fn faults_not_recorded() {
+        let mut c = Computer::new(&[1101, 1, 1, 5, 0]);
+        c.record_history();
+        assert!(c.try_run().is_err());
+        assert_eq!(c.history().unwrap().len(), 1);
+
+        assert!(c.step_back());
+        assert_eq!(c.memory(), &vec![1101, 1, 1, 5, 0]);
+        assert_eq!(c.program_counter(), 0);
+    }
+}
@@ -0,0 +1,226 @@
+use crate::{Intcode, IntcodeError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Opcode {
+    Add,
+    Mul,
+    Input,
+    Output,
+    JumpIfTrue,
+    JumpIfFalse,
+    LessThan,
+    Equals,
+    AdjustBase,
+    Halt,
+}
+
+impl Opcode {
+    pub fn from_code(code: Intcode) -> Option<Self> {
+        use Opcode::*;
+
+        Some(match code {
+            1 => Add,
+            2 => Mul,
+            3 => Input,
+            4 => Output,
+            5 => JumpIfTrue,
+            6 => JumpIfFalse,
+            7 => LessThan,
+            8 => Equals,
+            9 => AdjustBase,
+            99 => Halt,
+            _ => return None,
+        })
+    }
+
+    pub fn code(self) -> Intcode {
+        use Opcode::*;
+
+        match self {
+            Add => 1,
+            Mul => 2,
+            Input => 3,
+            Output => 4,
+            JumpIfTrue => 5,
+            JumpIfFalse => 6,
+            LessThan => 7,
+            Equals => 8,
+            AdjustBase => 9,
+            Halt => 99,
+        }
+    }
+
+    pub fn mnemonic(self) -> &'static str {
+        use Opcode::*;
+
+        match self {
+            Add => "ADD",
+            Mul => "MUL",
+            Input => "IN",
+            Output => "OUT",
+            JumpIfTrue => "JT",
+            JumpIfFalse => "JF",
+            LessThan => "LT",
+            Equals => "EQ",
+            AdjustBase => "ARB",
+            Halt => "HLT",
+        }
+    }
+
+    pub fn from_mnemonic(mnemonic: &str) -> Option<Self> {
+        use Opcode::*;
+
+        [
+            Add,
+            Mul,
+            Input,
+            Output,
+            JumpIfTrue,
+            JumpIfFalse,
+            LessThan,
+            Equals,
+            AdjustBase,
+            Halt,
+        ]
+        .iter()
+        .copied()
+        .find(|op| op.mnemonic().eq_ignore_ascii_case(mnemonic))
+    }
+
+    /// Number of parameters following the opcode
+    pub fn params(self) -> usize {
+        use Opcode::*;
+
+        match self {
+            Add | Mul | LessThan | Equals => 3,
+            JumpIfTrue | JumpIfFalse => 2,
+            Input | Output | AdjustBase => 1,
+            Halt => 0,
+        }
+    }
+
+    /// Whether the last parameter is an address that gets written to
+    pub fn writes(self) -> bool {
+        use Opcode::*;
+
+        matches!(self, Add | Mul | Input | LessThan | Equals)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ParamMode {
+    Position,
+    Immediate,
+    Relative,
+}
+
+impl ParamMode {
+    pub fn from_code(code: Intcode) -> Option<Self> {
+        Some(match code {
+            0 => ParamMode::Position,
+            1 => ParamMode::Immediate,
+            2 => ParamMode::Relative,
+            _ => return None,
+        })
+    }
+
+    pub fn code(self) -> Intcode {
+        match self {
+            ParamMode::Position => 0,
+            ParamMode::Immediate => 1,
+            ParamMode::Relative => 2,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Instruction {
+    pub opcode: Opcode,
+    pub modes: [ParamMode; 3],
+}
+
+impl Instruction {
+    /// Decode an instruction word found at `pc`. Modes of parameters the opcode does not use
+    /// must be zero, and are left as `Position`.
+    pub fn decode(pc: usize, word: Intcode) -> Result<Self, IntcodeError> {
+        let opcode = Opcode::from_code(word % 100)
+            .ok_or(IntcodeError::UnknownOpcode { pc, opcode: word })?;
+
+        let params = opcode.params();
+        let mut modes = [ParamMode::Position; 3];
+        let mut digits = word / 100;
+        for (idx, mode) in modes.iter_mut().enumerate().take(params) {
+            // the last parameter takes every remaining digit so oversized words are rejected
+            let code = if idx + 1 == params {
+                digits
+            } else {
+                digits % 10
+            };
+            *mode =
+                ParamMode::from_code(code).ok_or(IntcodeError::InvalidMode { pc, mode: code })?;
+            digits /= 10;
+        }
+        if params == 0 && digits != 0 {
+            return Err(IntcodeError::InvalidMode { pc, mode: digits });
+        }
+
+        Ok(Self { opcode, modes })
+    }
+
+    /// Length of the instruction including the opcode word
+    pub fn size(&self) -> usize {
+        self.opcode.params() + 1
+    }
+
+    pub fn encode(&self) -> Intcode {
+        self.modes
+            .iter()
+            .rev()
+            .fold(0, |acc, mode| acc * 10 + mode.code())
+            * 100
+            + self.opcode.code()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn decode() {
+        use ParamMode::*;
+
+        for case in [
+            (1002, Opcode::Mul, [Position, Immediate, Position]),
+            (21101, Opcode::Add, [Immediate, Immediate, Relative]),
+            (204, Opcode::Output, [Relative, Position, Position]),
+            (99, Opcode::Halt, [Position, Position, Position]),
+            (1105, Opcode::JumpIfTrue, [Immediate, Immediate, Position]),
+        ]
+        .iter()
+        {
+            let ins = Instruction::decode(0, case.0).unwrap();
+            assert_eq!(ins.opcode, case.1);
+            assert_eq!(ins.modes, case.2);
+            assert_eq!(ins.encode(), case.0);
+        }
+    }
+
+    #[test]
+    fn decode_errors() {
+        for case in [
+            (0, IntcodeError::UnknownOpcode { pc: 7, opcode: 0 }),
+            (-1, IntcodeError::UnknownOpcode { pc: 7, opcode: -1 }),
+            (-99, IntcodeError::UnknownOpcode { pc: 7, opcode: -99 }),
+            (304, IntcodeError::InvalidMode { pc: 7, mode: 3 }),
+            (110_001, IntcodeError::InvalidMode { pc: 7, mode: 11 }),
+            (100_104, IntcodeError::InvalidMode { pc: 7, mode: 1001 }),
+            (11_105, IntcodeError::InvalidMode { pc: 7, mode: 11 }),
+            (10_199, IntcodeError::InvalidMode { pc: 7, mode: 101 }),
+        ]
+        .iter()
+        {
+            assert_eq!(Instruction::decode(7, case.0), Err(case.1.clone()));
+        }
+    }
+}
@@ -0,0 +1,169 @@
+use crate::trace::{NoTrace, Tracer};
+use crate::{Computer, ComputerStatus, IntcodeError, Memory, Word};
+use std::convert::TryInto;
+
+/// Supplies a value whenever the program executes `IN`. Returning `None` pauses the run
+/// with the computer left waiting for input.
+pub trait Input<W> {
+    fn provide(&mut self) -> Option<W>;
+}
+
+impl<W, F: FnMut() -> Option<W>> Input<W> for F {
+    fn provide(&mut self) -> Option<W> {
+        self()
+    }
+}
+
+impl<W> Input<W> for Box<dyn Input<W> + '_> {
+    fn provide(&mut self) -> Option<W> {
+        (**self).provide()
+    }
+}
+
+/// Input taken from an iterator, see `from_iter`
+pub struct FromIter<I>(I);
+
+impl<I: Iterator> Input<I::Item> for FromIter<I> {
+    fn provide(&mut self) -> Option<I::Item> {
+        self.0.next()
+    }
+}
+
+pub fn from_iter<I: IntoIterator>(iter: I) -> FromIter<I::IntoIter> {
+    FromIter(iter.into_iter())
+}
+
+/// Iterator over everything a computer outputs, see `Computer::outputs`
+pub struct Outputs<'a, W, M, I, T> {
+    computer: &'a mut Computer<W, M>,
+    input: I,
+    tracer: T,
+    error: Option<IntcodeError>,
+}
+
+impl<'a, W, M, I, T> Outputs<'a, W, M, I, T> {
+    /// Ok if the program halted or ran out of input, or the fault that stopped it
+    pub fn finish(self) -> Result<(), IntcodeError> {
+        self.error.map_or(Ok(()), Err)
+    }
+}
+
+impl<'a, W: Word, M: Memory<W>, I: Input<W>, T: Tracer<W>> Iterator for Outputs<'a, W, M, I, T> {
+    type Item = W;
+
+    fn next(&mut self) -> Option<W> {
+        loop {
+            if let Some(value) = self.computer.output.pop_front() {
+                return Some(value);
+            }
+            if self.error.is_some() {
+                return None;
+            }
+
+            match self.computer.try_run_traced(&mut self.tracer) {
+                Ok(ComputerStatus::ReturnedValue) | Ok(ComputerStatus::BudgetExhausted) => (),
+                Ok(ComputerStatus::WaitingForInput) => {
+                    self.computer.input_add(self.input.provide()?)
+                }
+                Ok(ComputerStatus::Halt) => return None,
+                Err(e) => self.error = Some(e),
+            }
+        }
+    }
+}
+
+impl<W: Word, M: Memory<W>> Computer<W, M> {
+    /// Run the program lazily, feeding it from `input` and yielding its outputs. Iteration
+    /// ends when the program halts, faults or `input` runs dry; `Outputs::finish` tells which.
+    pub fn outputs<I: Input<W>>(&mut self, input: I) -> Outputs<'_, W, M, I, NoTrace> {
+        self.outputs_traced(input, NoTrace)
+    }
+
+    pub fn outputs_traced<I: Input<W>, T: Tracer<W>>(
+        &mut self,
+        input: I,
+        tracer: T,
+    ) -> Outputs<'_, W, M, I, T> {
+        Outputs {
+            computer: self,
+            input,
+            tracer,
+            error: None,
+        }
+    }
+}
+
+/// Iterator over fixed size groups of items, see `Grouping::grouped`
+pub struct Grouped<I, const N: usize>(I);
+
+impl<I: Iterator, const N: usize> Iterator for Grouped<I, N> {
+    type Item = [I::Item; N];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let group: Vec<_> = self.0.by_ref().take(N).collect();
+        group.try_into().ok()
+    }
+}
+
+pub trait Grouping: Iterator + Sized {
+    /// Collect items into arrays of `N`, such as day 13's `[x, y, tile]`. A trailing
+    /// incomplete group is dropped.
+    fn grouped<const N: usize>(self) -> Grouped<Self, N> {
+        Grouped(self)
+    }
+}
+
+impl<I: Iterator> Grouping for I {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Intcode;
+
+    // outputs each input twice until it reads a 0
+    const DOUBLE: [Intcode; 14] = [3, 13, 1006, 13, 12, 4, 13, 4, 13, 1105, 1, 0, 99, 0];
+
+    #[test]
+    fn providers() {
+        let mut c = Computer::new(&DOUBLE);
+        let mut next = 0;
+        let outputs: Vec<_> = c
+            .outputs(|| {
+                next = (next + 1) % 3;
+                Some(next)
+            })
+            .collect();
+        assert_eq!(outputs, vec![1, 1, 2, 2]);
+
+        let mut c = Computer::new(&DOUBLE);
+        let mut outputs = c.outputs(from_iter(vec![7, 8]));
+        assert_eq!(outputs.by_ref().collect::<Vec<_>>(), vec![7, 7, 8, 8]);
+        assert_eq!(outputs.finish(), Ok(()));
+        assert_eq!(c.run(), ComputerStatus::WaitingForInput);
+
+        let input: Box<dyn Input<Intcode>> = Box::new(from_iter(vec![5, 0]));
+        assert_eq!(c.outputs(input).collect::<Vec<_>>(), vec![5, 5]);
+        assert!(c.halted);
+    }
+
+    #[test]
+    fn grouped() {
+        let mut c = Computer::new(&DOUBLE);
+        let pairs: Vec<_> = c.outputs(from_iter(vec![1, 2, 3])).grouped().collect();
+        assert_eq!(pairs, vec![[1, 1], [2, 2], [3, 3]]);
+
+        let triples: Vec<[Intcode; 3]> = (1..=7).grouped().collect();
+        assert_eq!(triples, vec![[1, 2, 3], [4, 5, 6]]);
+    }
+
+    #[test]
+    fn fault() {
+        let mut c = Computer::new(&[104, 1, 104, 2, 42]);
+        let mut outputs = c.outputs(|| None);
+        assert_eq!(outputs.by_ref().collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(
+            outputs.finish(),
+            Err(IntcodeError::UnknownOpcode { pc: 4, opcode: 42 })
+        );
+    }
+}
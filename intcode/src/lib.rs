@@ -0,0 +1,643 @@
+pub mod ascii;
+pub mod asm;
+pub mod asynchronous;
+pub mod blocks;
+pub mod budget;
+pub mod cfg;
+pub mod coverage;
+pub mod debugger;
+pub mod decompile;
+pub mod disasm;
+mod error;
+pub mod fuzz;
+pub mod history;
+mod instruction;
+pub mod io;
+pub mod memory;
+pub mod network;
+pub mod profile;
+pub mod snapshot;
+pub mod symbolic;
+pub mod trace;
+pub mod watch;
+mod word;
+
+pub use error::IntcodeError;
+pub use instruction::{Instruction, Opcode, ParamMode};
+pub use memory::{Memory, PagedMemory};
+pub use snapshot::{Snapshot, SnapshotError};
+pub use word::Word;
+
+use coverage::Coverage;
+use history::History;
+use profile::Profiler;
+use trace::{Event, NoTrace, Tracer};
+use watch::{CodeWatch, CodeWrite};
+
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::io::Read;
+
+pub type Intcode = i64;
+
+#[derive(Clone)]
+pub struct Computer<W = Intcode, M = Vec<W>> {
+    input: VecDeque<W>,
+    pub output: VecDeque<W>,
+    memory: M,
+    program_counter: Cell<usize>,
+    current_instruction: usize,
+    base_offset: isize,
+    checked: bool,
+    watch: Option<Box<CodeWatch<W>>>,
+    history: Option<Box<History<W>>>,
+    profiler: Option<Box<Profiler>>,
+    coverage: Option<Box<Coverage>>,
+    pub halted: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ComputerStatus {
+    Halt,
+    WaitingForInput,
+    ReturnedValue,
+    /// Out of steps or cancelled, see `budget`; running again resumes
+    BudgetExhausted,
+}
+
+impl Computer {
+    pub fn new(program: &[Intcode]) -> Self {
+        Self::with_memory(program.to_vec())
+    }
+}
+
+impl<W: Word, M: Memory<W>> Computer<W, M> {
+    /// Start a computer on memory already holding the program
+    pub fn with_memory(memory: M) -> Self {
+        Self {
+            input: VecDeque::new(),
+            output: VecDeque::new(),
+            memory,
+            program_counter: Cell::new(0),
+            current_instruction: 0,
+            halted: false,
+            base_offset: 0,
+            checked: false,
+            watch: None,
+            history: None,
+            profiler: None,
+            coverage: None,
+        }
+    }
+
+    /// In checked mode arithmetic that overflows the word type fails with
+    /// `IntcodeError::Overflow` instead of wrapping
+    pub fn set_checked(&mut self, checked: bool) {
+        self.checked = checked;
+    }
+
+    /// Watch for self-modifying code: writes into addresses that have been, or later are,
+    /// executed. They are logged in `code_writes`, or in strict mode fail with
+    /// `IntcodeError::SelfModifying` instead.
+    pub fn watch_code(&mut self, strict: bool) {
+        self.watch = Some(Box::new(CodeWatch::new(strict)));
+    }
+
+    pub fn code_writes(&self) -> &[CodeWrite<W>] {
+        self.watch.as_ref().map_or(&[], |watch| watch.log())
+    }
+
+    pub fn input_add(&mut self, input: W) {
+        self.input.push_back(input)
+    }
+
+    pub fn input_add_all<'a, I: IntoIterator<Item = &'a W>>(&mut self, input: I)
+    where
+        W: 'a,
+    {
+        self.input.extend(input.into_iter().cloned())
+    }
+
+    pub fn output_get(&mut self) -> W {
+        self.try_output_get().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_output_get(&mut self) -> Result<W, IntcodeError> {
+        self.output.pop_front().ok_or(IntcodeError::OutputUnderflow)
+    }
+
+    pub fn program_counter(&self) -> usize {
+        self.program_counter.get()
+    }
+
+    pub fn base_offset(&self) -> isize {
+        self.base_offset
+    }
+
+    pub fn memory(&self) -> &M {
+        &self.memory
+    }
+
+    pub fn input(&self) -> &VecDeque<W> {
+        &self.input
+    }
+
+    fn overflow(&self) -> IntcodeError {
+        IntcodeError::Overflow {
+            pc: self.current_instruction,
+        }
+    }
+
+    fn add(&self, a: W, b: W) -> Result<W, IntcodeError> {
+        if self.checked {
+            a.checked_add(&b).ok_or_else(|| self.overflow())
+        } else {
+            Ok(a + b)
+        }
+    }
+
+    fn mul(&self, a: W, b: W) -> Result<W, IntcodeError> {
+        if self.checked {
+            a.checked_mul(&b).ok_or_else(|| self.overflow())
+        } else {
+            Ok(a * b)
+        }
+    }
+
+    fn relative(&self, offset: W) -> Result<W, IntcodeError> {
+        let base = W::from_isize(self.base_offset).ok_or_else(|| self.overflow())?;
+        self.add(base, offset)
+    }
+
+    fn address(&self, location: &W) -> Result<usize, IntcodeError> {
+        location.to_usize().ok_or_else(|| match location.to_i64() {
+            Some(address) if address < 0 => IntcodeError::NegativeAddress {
+                pc: self.current_instruction,
+                address,
+            },
+            _ => self.overflow(),
+        })
+    }
+
+    fn write_memory<T: Tracer<W> + ?Sized>(
+        &mut self,
+        tracer: &mut T,
+        mode: ParamMode,
+        value: W,
+    ) -> Result<(), IntcodeError> {
+        let pos = self.next_pc();
+        let mpos = self.memory.read(pos);
+        let mpos = match mode {
+            ParamMode::Position => self.address(&mpos)?,
+            ParamMode::Immediate => {
+                return Err(IntcodeError::ImmediateWrite {
+                    pc: self.current_instruction,
+                })
+            }
+            ParamMode::Relative => self.address(&self.relative(mpos)?)?,
+        };
+
+        if let Some(watch) = &mut self.watch {
+            watch.write(self.current_instruction, mpos, &value)?;
+        }
+        if let Some(history) = &mut self.history {
+            history.write(mpos, self.memory.read(mpos), value.clone());
+        }
+        if tracer.enabled() {
+            tracer.trace(&Event::Write {
+                address: mpos,
+                old: self.memory.read(mpos),
+                value: value.clone(),
+            });
+        }
+        self.memory.write(mpos, value);
+        Ok(())
+    }
+
+    fn read_memory(&self, pos: usize, mode: ParamMode) -> Result<W, IntcodeError> {
+        let mpos = self.memory.read(pos);
+
+        let location = match mode {
+            ParamMode::Position => mpos.to_usize(),
+            ParamMode::Immediate => Some(pos),
+            ParamMode::Relative => self.relative(mpos)?.to_usize(),
+        };
+
+        Ok(location.map_or_else(W::zero, |location| self.memory.read(location)))
+    }
+
+    /// Operands as reported to a tracer: values read, or the address for a written parameter
+    fn operands(&self, instruction: &Instruction) -> Result<[W; 3], IntcodeError> {
+        let count = instruction.opcode.params();
+        let mut operands = [W::zero(), W::zero(), W::zero()];
+
+        for (idx, operand) in operands.iter_mut().enumerate().take(count) {
+            let pos = self.current_instruction + 1 + idx;
+            let mode = instruction.modes[idx];
+
+            *operand = if instruction.opcode.writes() && idx == count - 1 {
+                let raw = self.memory.read(pos);
+                match mode {
+                    ParamMode::Relative => self.relative(raw)?,
+                    _ => raw,
+                }
+            } else {
+                self.read_memory(pos, mode)?
+            };
+        }
+
+        Ok(operands)
+    }
+
+    fn read_instruction(&mut self) -> W {
+        self.current_instruction = self.program_counter.get();
+        self.memory.read(self.next_pc())
+    }
+
+    fn next_pc(&self) -> usize {
+        let current = self.program_counter.get();
+        self.program_counter.set(current + 1);
+        current
+    }
+
+    fn prev_pc(&self) -> usize {
+        let current = self.program_counter.get();
+        self.program_counter.set(current - 1);
+        current
+    }
+
+    fn set_pc(&self, pos: usize) {
+        self.program_counter.set(pos);
+    }
+
+    fn jump<T: Tracer<W> + ?Sized>(
+        &mut self,
+        tracer: &mut T,
+        target: W,
+    ) -> Result<(), IntcodeError> {
+        let to = self.address(&target)?;
+        if let Some(profiler) = &mut self.profiler {
+            profiler.jump(self.current_instruction, to);
+        }
+        if let Some(coverage) = &mut self.coverage {
+            coverage.jump(to);
+        }
+        tracer.trace(&Event::Jump {
+            from: self.current_instruction,
+            to,
+        });
+        self.set_pc(to);
+        Ok(())
+    }
+
+    pub fn run(&mut self) -> ComputerStatus {
+        self.try_run().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_run(&mut self) -> Result<ComputerStatus, IntcodeError> {
+        self.try_run_traced(&mut NoTrace)
+    }
+
+    pub fn try_run_traced<T: Tracer<W> + ?Sized>(
+        &mut self,
+        tracer: &mut T,
+    ) -> Result<ComputerStatus, IntcodeError> {
+        loop {
+            if let Some(status) = self.step_traced(tracer)? {
+                return Ok(status);
+            }
+        }
+    }
+
+    /// Execute a single instruction, returning the status `run` would stop with, if any
+    pub fn step(&mut self) -> Result<Option<ComputerStatus>, IntcodeError> {
+        self.step_traced(&mut NoTrace)
+    }
+
+    pub fn step_traced<T: Tracer<W> + ?Sized>(
+        &mut self,
+        tracer: &mut T,
+    ) -> Result<Option<ComputerStatus>, IntcodeError> {
+        // leave the program counter on the faulting instruction
+        let result = self
+            .execute(tracer)
+            .inspect_err(|_| self.set_pc(self.current_instruction));
+        if let Some(history) = &mut self.history {
+            history.finish(result.is_ok());
+        }
+        result
+    }
+
+    fn execute<T: Tracer<W> + ?Sized>(
+        &mut self,
+        tracer: &mut T,
+    ) -> Result<Option<ComputerStatus>, IntcodeError> {
+        let word = self.read_instruction();
+        // instructions are decoded as i64, anything wider cannot be one
+        let word = word.to_i64().ok_or_else(|| self.overflow())?;
+        let instruction = Instruction::decode(self.current_instruction, word)?;
+        let [mode_a, mode_b, mode_c] = instruction.modes;
+
+        if instruction.opcode == Opcode::Input && self.input.is_empty() {
+            self.prev_pc();
+            return Ok(Some(ComputerStatus::WaitingForInput));
+        }
+
+        if let Some(watch) = &mut self.watch {
+            watch.execute(self.current_instruction, instruction.size())?;
+        }
+        if tracer.enabled() {
+            tracer.trace(&Event::Instruction {
+                pc: self.current_instruction,
+                instruction,
+                operands: self.operands(&instruction)?,
+            });
+        }
+        if let Some(profiler) = &mut self.profiler {
+            profiler.instruction(self.current_instruction, instruction);
+        }
+        if let Some(coverage) = &mut self.coverage {
+            coverage.instruction(self.current_instruction);
+        }
+        // running a halted machine only reports the halt again, there is nothing to undo
+        if let Some(history) = &mut self.history {
+            if !self.halted {
+                history.begin(
+                    self.current_instruction,
+                    self.base_offset,
+                    self.memory.extent(),
+                );
+            }
+        }
+
+        match instruction.opcode {
+            Opcode::Add => {
+                let a = self.read_memory(self.next_pc(), mode_a)?;
+                let b = self.read_memory(self.next_pc(), mode_b)?;
+                let sum = self.add(a, b)?;
+                self.write_memory(tracer, mode_c, sum)?;
+            }
+            Opcode::Mul => {
+                let a = self.read_memory(self.next_pc(), mode_a)?;
+                let b = self.read_memory(self.next_pc(), mode_b)?;
+                let product = self.mul(a, b)?;
+                self.write_memory(tracer, mode_c, product)?;
+            }
+            Opcode::Input => {
+                let a = self.input.front().cloned().unwrap();
+                tracer.trace(&Event::Input(a.clone()));
+                if let Some(history) = &mut self.history {
+                    history.input(a.clone());
+                }
+                self.write_memory(tracer, mode_a, a)?;
+                // only taken once stored, so a faulting write leaves it queued
+                self.input.pop_front();
+            }
+            Opcode::Output => {
+                let a = self.read_memory(self.next_pc(), mode_a)?;
+                tracer.trace(&Event::Output(a.clone()));
+                if let Some(history) = &mut self.history {
+                    history.output();
+                }
+                self.output.push_back(a);
+                return Ok(Some(ComputerStatus::ReturnedValue));
+            }
+            Opcode::JumpIfTrue => {
+                let a = self.read_memory(self.next_pc(), mode_a)?;
+                let b = self.read_memory(self.next_pc(), mode_b)?;
+                if let Some(coverage) = &mut self.coverage {
+                    coverage.branched(self.current_instruction, &instruction, !a.is_zero());
+                }
+                if !a.is_zero() {
+                    self.jump(tracer, b)?;
+                }
+            }
+            Opcode::JumpIfFalse => {
+                let a = self.read_memory(self.next_pc(), mode_a)?;
+                let b = self.read_memory(self.next_pc(), mode_b)?;
+                if let Some(coverage) = &mut self.coverage {
+                    coverage.branched(self.current_instruction, &instruction, a.is_zero());
+                }
+                if a.is_zero() {
+                    self.jump(tracer, b)?;
+                }
+            }
+            Opcode::LessThan => {
+                let a = self.read_memory(self.next_pc(), mode_a)?;
+                let b = self.read_memory(self.next_pc(), mode_b)?;
+                let flag = if a < b { W::one() } else { W::zero() };
+                self.write_memory(tracer, mode_c, flag)?;
+            }
+            Opcode::Equals => {
+                let a = self.read_memory(self.next_pc(), mode_a)?;
+                let b = self.read_memory(self.next_pc(), mode_b)?;
+                let flag = if a == b { W::one() } else { W::zero() };
+                self.write_memory(tracer, mode_c, flag)?;
+            }
+            Opcode::AdjustBase => {
+                let a = self.read_memory(self.next_pc(), mode_a)?;
+                let base = self.relative(a)?;
+                self.base_offset = base.to_isize().ok_or_else(|| self.overflow())?;
+            }
+            Opcode::Halt => {
+                // stay on the halt so running again keeps halting
+                self.prev_pc();
+                self.halted = true;
+                return Ok(Some(ComputerStatus::Halt));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+pub fn read_input<R: Read>(input: &mut R) -> Result<Vec<Intcode>, String> {
+    let mut buffer = String::new();
+    if let Err(msg) = input.read_to_string(&mut buffer) {
+        return Err(msg.to_string());
+    }
+
+    buffer
+        .split(',')
+        .map(|s| s.trim())
+        .map(|s| {
+            s.parse::<_>()
+                .map_err(|e: std::num::ParseIntError| format!("{}: {}", e, s))
+        })
+        .collect::<Result<_, _>>()
+}
+
+pub fn run_program(instructions: &[Intcode], input: Intcode) -> Intcode {
+    let mut c = Computer::new(instructions);
+    c.input_add(input);
+    c.run();
+    c.output_get()
+}
+
+pub fn run_program_n(instructions: &[Intcode], input: &[Intcode]) -> Intcode {
+    let mut c = Computer::new(instructions);
+    c.input_add_all(input.iter());
+    c.run();
+    c.output_get()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parsing_file() {
+        let mut input: &[u8] = b"123, 9,45678 ,12,234,2,3,4";
+        assert_eq!(
+            read_input(&mut input),
+            Ok(vec![123, 9, 45678, 12, 234, 2, 3, 4])
+        );
+    }
+
+    #[test]
+    fn reddit() {
+        for case in [
+            (vec![109, -1, 4, 1, 99], -1),
+            (vec![109, -1, 104, 1, 99], 1),
+            (vec![109, -1, 204, 1, 99], 109),
+            (vec![109, 1, 9, 2, 204, -6, 99], 204),
+            (vec![109, 1, 109, 9, 204, -6, 99], 204),
+            (vec![109, 1, 209, -1, 204, -106, 99], 204),
+            (vec![109, 1, 3, 3, 204, 2, 99], 1),
+        ]
+        .iter()
+        {
+            assert_eq!(run_program(&case.0, 1), case.1);
+            assert_eq!(run_program(&case.0, 1), run_program_n(&case.0, &[1]));
+        }
+    }
+
+    #[test]
+    fn debug() {
+        assert_eq!(run_program(&[109, 1, 203, 2, 204, 2, 99], 42), 42);
+    }
+
+    #[test]
+    fn resume() {
+        let mut c = Computer::new(&[3, 11, 3, 12, 1, 11, 12, 13, 4, 13, 99]);
+        assert_eq!(c.run(), ComputerStatus::WaitingForInput);
+        c.input_add(3);
+        assert_eq!(c.run(), ComputerStatus::WaitingForInput);
+        c.input_add(4);
+        assert_eq!(c.run(), ComputerStatus::ReturnedValue);
+        assert_eq!(c.output_get(), 7);
+        assert_eq!(c.run(), ComputerStatus::Halt);
+        assert!(c.halted);
+        assert_eq!(c.run(), ComputerStatus::Halt);
+        assert_eq!(c.program_counter(), 10);
+    }
+
+    #[test]
+    fn errors() {
+        for case in [
+            (vec![42], IntcodeError::UnknownOpcode { pc: 0, opcode: 42 }),
+            (
+                vec![104, 1, -3],
+                IntcodeError::UnknownOpcode { pc: 2, opcode: -3 },
+            ),
+            (
+                vec![301, 0, 0, 0],
+                IntcodeError::InvalidMode { pc: 0, mode: 3 },
+            ),
+            (
+                vec![1, 0, 0, -1],
+                IntcodeError::NegativeAddress { pc: 0, address: -1 },
+            ),
+            (vec![10001, 0, 0, 0], IntcodeError::ImmediateWrite { pc: 0 }),
+            (
+                vec![1105, 1, -7],
+                IntcodeError::NegativeAddress { pc: 0, address: -7 },
+            ),
+            (vec![200_001], IntcodeError::InvalidMode { pc: 0, mode: 20 }),
+        ]
+        .iter()
+        {
+            let mut c = Computer::new(&case.0);
+            while let Ok(ComputerStatus::ReturnedValue) = c.try_run() {}
+            assert_eq!(c.try_run(), Err(case.1.clone()));
+            assert_eq!(c.try_run(), Err(case.1.clone()));
+        }
+    }
+
+    #[test]
+    fn word_types() {
+        use num::BigInt;
+
+        // square the input twice: x^4
+        let program = [3, 13, 2, 13, 13, 13, 2, 13, 13, 13, 4, 13, 99];
+
+        let mut c = Computer::<i128>::with_memory(program.iter().map(|&w| w.into()).collect());
+        c.input_add(1 << 20);
+        c.run();
+        assert_eq!(c.output_get(), 1 << 80);
+
+        let mut c = Computer::<BigInt>::with_memory(program.iter().map(|&w| w.into()).collect());
+        c.input_add(BigInt::from(1) << 40);
+        c.run();
+        assert_eq!(c.output_get(), BigInt::from(1) << 160);
+
+        let mut c = Computer::new(&program);
+        c.set_checked(true);
+        c.input_add(1 << 20);
+        assert_eq!(c.try_run(), Err(IntcodeError::Overflow { pc: 6 }));
+        assert_eq!(c.memory()[13], 1 << 40);
+    }
+
+    #[test]
+    fn input_kept_on_fault() {
+        let mut c = Computer::new(&[203, -1, 99]);
+        c.input_add(7);
+        assert_eq!(
+            c.try_run(),
+            Err(IntcodeError::NegativeAddress { pc: 0, address: -1 })
+        );
+        assert_eq!(c.input(), &vec![7]);
+
+        let mut c = Computer::new(&[103, 0, 99]);
+        c.input_add(7);
+        assert_eq!(c.try_run(), Err(IntcodeError::ImmediateWrite { pc: 0 }));
+        assert_eq!(c.input(), &vec![7]);
+    }
+
+    #[test]
+    fn output_underflow() {
+        let mut c = Computer::new(&[99]);
+        assert_eq!(c.try_run(), Ok(ComputerStatus::Halt));
+        assert_eq!(c.try_output_get(), Err(IntcodeError::OutputUnderflow));
+    }
+
+    #[test]
+    fn simple_example() {
+        for case in [(2, 1), (1, 1), (0, 0)].iter() {
+            assert_eq!(
+                run_program(
+                    &[3, 12, 6, 12, 15, 1, 13, 14, 13, 4, 13, 99, -1, 0, 1, 9],
+                    case.0,
+                ),
+                case.1
+            );
+        }
+    }
+
+    #[test]
+    fn sample_4() {
+        for case in [(9, 1001), (8, 1000), (7, 999)].iter() {
+            assert_eq!(
+                run_program(
+                    &[
+                        3, 21, 1008, 21, 8, 20, 1005, 20, 22, 107, 8, 21, 20, 1006, 20, 31, 1106,
+                        0, 36, 98, 0, 0, 1002, 21, 125, 20, 4, 20, 1105, 1, 46, 104, 999, 1105, 1,
+                        46, 1101, 1000, 1, 20, 4, 20, 1105, 1, 46, 98, 99,
+                    ],
+                    case.0,
+                ),
+                case.1
+            );
+        }
+    }
+}
@@ -0,0 +1,201 @@
+use crate::{Intcode, Word};
+use std::collections::BTreeMap;
+
+const PAGE_SIZE: usize = 1024;
+
+/// Backing store for a `Computer`. Cells that were never written read as 0.
+pub trait Memory<W = Intcode> {
+    fn read(&self, address: usize) -> W;
+    fn write(&mut self, address: usize, value: W);
+
+    /// The allocated stretches of memory as `(start address, words)`, in address order
+    fn regions(&self) -> Vec<(usize, &[W])>;
+
+    /// One past the highest allocated address
+    fn extent(&self) -> usize;
+
+    /// Free what was allocated from `extent` on, to undo growth
+    fn truncate(&mut self, extent: usize);
+
+    /// Words newly allocated by writing `len` words from `start`, to vet untrusted input before
+    /// loading it. `start + len` must not overflow.
+    fn growth(&self, start: usize, len: usize) -> usize;
+}
+
+/// Plain contiguous memory, grown up to the highest address written
+impl<W: Word> Memory<W> for Vec<W> {
+    fn read(&self, address: usize) -> W {
+        self.get(address).cloned().unwrap_or_else(W::zero)
+    }
+
+    fn write(&mut self, address: usize, value: W) {
+        if address >= self.len() {
+            self.resize(address + 1, W::zero());
+        }
+        self[address] = value;
+    }
+
+    fn regions(&self) -> Vec<(usize, &[W])> {
+        vec![(0, &self[..])]
+    }
+
+    fn extent(&self) -> usize {
+        self.len()
+    }
+
+    fn truncate(&mut self, extent: usize) {
+        Vec::truncate(self, extent)
+    }
+
+    fn growth(&self, start: usize, len: usize) -> usize {
+        match len {
+            0 => 0,
+            _ => (start + len).saturating_sub(self.len()),
+        }
+    }
+}
+
+/// Allocates fixed size pages on first write, so programs may scribble on far away addresses
+#[derive(Debug, Clone, PartialEq)]
+pub struct PagedMemory<W = Intcode> {
+    pages: BTreeMap<usize, Box<[W]>>,
+}
+
+impl<W> Default for PagedMemory<W> {
+    fn default() -> Self {
+        Self {
+            pages: BTreeMap::new(),
+        }
+    }
+}
+
+impl<W: Word> PagedMemory<W> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of pages allocated so far
+    pub fn pages(&self) -> usize {
+        self.pages.len()
+    }
+}
+
+impl<W: Word> From<&[W]> for PagedMemory<W> {
+    fn from(program: &[W]) -> Self {
+        let mut memory = Self::new();
+        for (address, value) in program.iter().enumerate() {
+            memory.write(address, value.clone());
+        }
+        memory
+    }
+}
+
+impl<W: Word> Memory<W> for PagedMemory<W> {
+    fn read(&self, address: usize) -> W {
+        self.pages
+            .get(&(address / PAGE_SIZE))
+            .map_or_else(W::zero, |page| page[address % PAGE_SIZE].clone())
+    }
+
+    fn write(&mut self, address: usize, value: W) {
+        let page = self
+            .pages
+            .entry(address / PAGE_SIZE)
+            .or_insert_with(|| vec![W::zero(); PAGE_SIZE].into_boxed_slice());
+        page[address % PAGE_SIZE] = value;
+    }
+
+    fn regions(&self) -> Vec<(usize, &[W])> {
+        self.pages
+            .iter()
+            .map(|(&idx, page)| (idx * PAGE_SIZE, &page[..]))
+            .collect()
+    }
+
+    fn extent(&self) -> usize {
+        self.pages
+            .keys()
+            .next_back()
+            .map_or(0, |&idx| (idx * PAGE_SIZE).saturating_add(PAGE_SIZE))
+    }
+
+    fn truncate(&mut self, extent: usize) {
+        // only whole pages are freed
+        self.pages.split_off(&extent.div_ceil(PAGE_SIZE));
+    }
+
+    fn growth(&self, start: usize, len: usize) -> usize {
+        match len {
+            0 => 0,
+            _ => {
+                let pages = start / PAGE_SIZE..=(start + len - 1) / PAGE_SIZE;
+                let new = pages.filter(|idx| !self.pages.contains_key(idx)).count();
+                new * PAGE_SIZE
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Computer, ComputerStatus};
+
+    #[test]
+    fn paged() {
+        let mut memory = PagedMemory::<Intcode>::from(&[1, 2, 3][..]);
+        assert_eq!(memory.pages(), 1);
+        assert_eq!(memory.read(2), 3);
+        assert_eq!(memory.read(5000), 0);
+
+        let far = usize::MAX - 1;
+        memory.write(far, 7);
+        assert_eq!(memory.read(far), 7);
+        assert_eq!(memory.read(far - PAGE_SIZE), 0);
+        assert_eq!(memory.pages(), 2);
+
+        let regions = memory.regions();
+        assert_eq!(regions[0].0, 0);
+        assert_eq!(regions[1].0, (far / PAGE_SIZE) * PAGE_SIZE);
+        assert!(regions.iter().all(|r| r.1.len() == PAGE_SIZE));
+
+        assert_eq!(memory.extent(), usize::MAX);
+        assert_eq!(memory.growth(far, 1), 0);
+        assert_eq!(memory.growth(PAGE_SIZE - 1, 2), PAGE_SIZE);
+        memory.truncate(far);
+        assert_eq!(memory.pages(), 2);
+        memory.truncate(PAGE_SIZE + 1);
+        assert_eq!(memory.pages(), 1);
+        assert_eq!(memory.extent(), PAGE_SIZE);
+    }
+
+    #[test]
+    fn far_write() {
+        // store the input at 10^12, then echo it back from there
+        let program: [Intcode; 5] = [3, 1_000_000_000_000, 4, 1_000_000_000_000, 99];
+        let mut c = Computer::with_memory(PagedMemory::from(&program[..]));
+        c.input_add(42);
+
+        assert_eq!(c.run(), ComputerStatus::ReturnedValue);
+        assert_eq!(c.output_get(), 42);
+        assert_eq!(c.run(), ComputerStatus::Halt);
+        assert_eq!(c.memory().pages(), 2);
+    }
+
+    #[test]
+    fn same_as_dense() {
+        // quine from day 9, which writes past the end of the program
+        let program: [Intcode; 16] = [
+            109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99,
+        ];
+
+        let mut dense = Computer::new(&program);
+        let mut paged = Computer::with_memory(PagedMemory::from(&program[..]));
+        while dense.run() != ComputerStatus::Halt {}
+        while paged.run() != ComputerStatus::Halt {}
+
+        assert_eq!(dense.output, program.to_vec());
+        assert_eq!(paged.output, dense.output);
+        assert_eq!(paged.memory().read(100), dense.memory().read(100));
+    }
+}
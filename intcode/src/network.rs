@@ -0,0 +1,409 @@
+use crate::{Computer, ComputerStatus, IntcodeError, Memory, Word};
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+
+/// Where each machine's outputs go. Every output is sent to all of a machine's targets.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Topology {
+    /// 0 -> 1 -> ... -> n-1
+    Pipeline,
+    /// A pipeline whose last machine feeds the first
+    Ring,
+    /// Every machine feeds every other machine
+    FullyConnected,
+    /// `targets[i]` lists the machines fed by machine `i`
+    Custom(Vec<Vec<usize>>),
+}
+
+/// A `Topology::Custom` that does not fit the machines it is given
+#[derive(Debug, Clone, PartialEq)]
+pub enum TopologyError {
+    /// There must be one target list per machine
+    Lists {
+        machines: usize,
+        lists: usize,
+    },
+    NoSuchTarget {
+        machine: usize,
+        target: usize,
+    },
+}
+
+impl fmt::Display for TopologyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TopologyError::Lists { machines, lists } => {
+                write!(f, "{} target lists for {} machines", lists, machines)
+            }
+            TopologyError::NoSuchTarget { machine, target } => {
+                write!(f, "machine {} targets missing machine {}", machine, target)
+            }
+        }
+    }
+}
+
+impl std::error::Error for TopologyError {}
+
+impl Topology {
+    fn targets(&self, count: usize) -> Result<Vec<Vec<usize>>, TopologyError> {
+        Ok(match self {
+            Topology::Pipeline => (0..count)
+                .map(|i| if i + 1 < count { vec![i + 1] } else { vec![] })
+                .collect(),
+            Topology::Ring => (0..count).map(|i| vec![(i + 1) % count]).collect(),
+            Topology::FullyConnected => (0..count)
+                .map(|i| (0..count).filter(|&j| j != i).collect())
+                .collect(),
+            Topology::Custom(targets) => {
+                if targets.len() != count {
+                    return Err(TopologyError::Lists {
+                        machines: count,
+                        lists: targets.len(),
+                    });
+                }
+                for (machine, list) in targets.iter().enumerate() {
+                    if let Some(&target) = list.iter().find(|&&t| t >= count) {
+                        return Err(TopologyError::NoSuchTarget { machine, target });
+                    }
+                }
+                targets.clone()
+            }
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    Halted,
+    /// Stopped waiting for input that could never arrive
+    Deadlocked,
+    Faulted(IntcodeError),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MachineReport<W> {
+    pub outputs: Vec<W>,
+    pub outcome: Outcome,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report<W> {
+    pub machines: Vec<MachineReport<W>>,
+}
+
+impl<W> Report<W> {
+    pub fn deadlocked(&self) -> bool {
+        self.machines
+            .iter()
+            .any(|m| m.outcome == Outcome::Deadlocked)
+    }
+}
+
+impl<W: fmt::Display> fmt::Display for Report<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (idx, machine) in self.machines.iter().enumerate() {
+            let outcome = match &machine.outcome {
+                Outcome::Halted => "halted".to_string(),
+                Outcome::Deadlocked => "deadlocked".to_string(),
+                Outcome::Faulted(e) => format!("faulted: {}", e),
+            };
+            let outputs: Vec<_> = machine.outputs.iter().map(|o| o.to_string()).collect();
+            writeln!(f, "{:>3}  {:<12}[{}]", idx, outcome, outputs.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// The queues between machines, and the bookkeeping to notice when none of them can make
+/// progress
+struct State<W> {
+    /// Values sent to each machine and not read yet, `None` once it has stopped
+    queues: Vec<Option<VecDeque<W>>>,
+    running: usize,
+    waiting: usize,
+    in_flight: usize,
+    deadlocked: bool,
+}
+
+impl<W> State<W> {
+    fn stuck(&mut self) -> bool {
+        if self.running > 0 && self.waiting == self.running && self.in_flight == 0 {
+            self.deadlocked = true;
+        }
+        self.deadlocked
+    }
+}
+
+struct Shared<W> {
+    state: Mutex<State<W>>,
+    /// Signalled whenever a value is sent or a machine stops
+    changed: Condvar,
+}
+
+/// A machine's connections to the rest of the network
+struct Link<W> {
+    machine: usize,
+    targets: Vec<usize>,
+    shared: Arc<Shared<W>>,
+}
+
+impl<W: Clone> Link<W> {
+    fn send(&self, value: &W) {
+        let mut guard = self.shared.state.lock().unwrap();
+        let state = &mut *guard;
+        for &target in &self.targets {
+            if let Some(queue) = &mut state.queues[target] {
+                queue.push_back(value.clone());
+                state.in_flight += 1;
+            }
+        }
+        self.shared.changed.notify_all();
+    }
+
+    /// Block until a value arrives, or `None` once the whole network is stuck
+    fn receive(&self) -> Option<W> {
+        let mut guard = self.shared.state.lock().unwrap();
+        guard.waiting += 1;
+
+        loop {
+            let state = &mut *guard;
+            if let Some(value) = state.queues[self.machine]
+                .as_mut()
+                .and_then(VecDeque::pop_front)
+            {
+                state.waiting -= 1;
+                state.in_flight -= 1;
+                return Some(value);
+            }
+            if state.stuck() {
+                // the others waiting have to find out too
+                self.shared.changed.notify_all();
+                return None;
+            }
+            guard = self.shared.changed.wait(guard).unwrap();
+        }
+    }
+
+    fn stop(self) {
+        let mut state = self.shared.state.lock().unwrap();
+        if let Some(queue) = state.queues[self.machine].take() {
+            state.in_flight -= queue.len();
+        }
+        state.running -= 1;
+        self.shared.changed.notify_all();
+    }
+}
+
+fn run_machine<W: Word, M: Memory<W>>(
+    mut computer: Computer<W, M>,
+    link: Link<W>,
+) -> MachineReport<W> {
+    let mut outputs = Vec::new();
+
+    let outcome = loop {
+        match computer.try_run() {
+            Ok(ComputerStatus::ReturnedValue) | Ok(ComputerStatus::BudgetExhausted) => {
+                while let Some(value) = computer.output.pop_front() {
+                    link.send(&value);
+                    outputs.push(value);
+                }
+            }
+            Ok(ComputerStatus::WaitingForInput) => match link.receive() {
+                Some(value) => computer.input_add(value),
+                None => break Outcome::Deadlocked,
+            },
+            Ok(ComputerStatus::Halt) => break Outcome::Halted,
+            Err(e) => break Outcome::Faulted(e),
+        }
+    };
+
+    link.stop();
+    MachineReport { outputs, outcome }
+}
+
+/// Run every machine on its own thread, wired together by `topology`, until each one has
+/// halted, faulted or is left waiting for input nobody will send. Inputs already queued on a
+/// machine, such as day 7 phase settings, are consumed first.
+pub fn run_network<W, M>(
+    machines: Vec<Computer<W, M>>,
+    topology: &Topology,
+) -> Result<Report<W>, TopologyError>
+where
+    W: Word + Send + 'static,
+    M: Memory<W> + Send + 'static,
+{
+    let targets = topology.targets(machines.len())?;
+    let shared = Arc::new(Shared {
+        state: Mutex::new(State {
+            queues: machines.iter().map(|_| Some(VecDeque::new())).collect(),
+            running: machines.len(),
+            waiting: 0,
+            in_flight: 0,
+            deadlocked: false,
+        }),
+        changed: Condvar::new(),
+    });
+
+    let handles: Vec<_> = machines
+        .into_iter()
+        .zip(targets)
+        .enumerate()
+        .map(|(machine, (computer, targets))| {
+            let link = Link {
+                machine,
+                targets,
+                shared: shared.clone(),
+            };
+            thread::spawn(move || run_machine(computer, link))
+        })
+        .collect();
+
+    Ok(Report {
+        machines: handles
+            .into_iter()
+            .map(|h| h.join().expect("machine thread panicked"))
+            .collect(),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Intcode;
+
+    const FEEDBACK: [Intcode; 29] = [
+        3, 26, 1001, 26, -4, 26, 3, 27, 1002, 27, 2, 27, 1, 27, 26, 27, 4, 27, 1001, 28, -1, 28,
+        1005, 28, 6, 99, 0, 0, 5,
+    ];
+
+    // add one to every input and pass it on, forever
+    const INCREMENT: [Intcode; 12] = [3, 11, 101, 1, 11, 11, 4, 11, 1105, 1, 0, 0];
+
+    fn machines(program: &[Intcode], inputs: &[&[Intcode]]) -> Vec<Computer> {
+        inputs
+            .iter()
+            .map(|input| {
+                let mut c = Computer::new(program);
+                c.input_add_all(input.iter());
+                c
+            })
+            .collect()
+    }
+
+    #[test]
+    fn ring() {
+        let amps = machines(&FEEDBACK, &[&[9, 0], &[8], &[7], &[6], &[5]]);
+        let report = run_network(amps, &Topology::Ring).unwrap();
+
+        assert!(!report.deadlocked());
+        assert!(report.machines.iter().all(|m| m.outcome == Outcome::Halted));
+        assert_eq!(report.machines[4].outputs.last(), Some(&139_629_729));
+    }
+
+    #[test]
+    fn pipeline_deadlock() {
+        let report = run_network(
+            machines(&INCREMENT, &[&[1, 5], &[], &[]]),
+            &Topology::Pipeline,
+        )
+        .unwrap();
+
+        assert!(report.deadlocked());
+        assert_eq!(
+            report
+                .machines
+                .iter()
+                .map(|m| &m.outputs)
+                .collect::<Vec<_>>(),
+            vec![&vec![2, 6], &vec![3, 7], &vec![4, 8]]
+        );
+        assert_eq!(
+            report.to_string(),
+            "  0  deadlocked  [2, 6]
+  1  deadlocked  [3, 7]
+  2  deadlocked  [4, 8]
+"
+        );
+    }
+
+    #[test]
+    fn target_stops_early() {
+        // 0 keeps sending to 1, which halts after its first input, then waits for a reply
+        let talker = crate::asm::assemble(
+            "
+            loop:   add  [n], #1, [n]
+                    out  [n]
+                    lt   [n], #1000, [t]
+                    jt   [t], #loop
+                    in   [t]
+                    hlt
+            n:      data 0
+            t:      data 0
+            ",
+        )
+        .unwrap();
+        let mut nodes = machines(&talker, &[&[]]);
+        nodes.extend(machines(&[3, 0, 99], &[&[]]));
+        let topology = Topology::Custom(vec![vec![1], vec![]]);
+
+        for _ in 0..20 {
+            let report = run_network(nodes.clone(), &topology).unwrap();
+            assert_eq!(report.machines[0].outcome, Outcome::Deadlocked);
+            assert_eq!(report.machines[0].outputs.len(), 1000);
+            assert_eq!(report.machines[1].outcome, Outcome::Halted);
+        }
+    }
+
+    #[test]
+    fn topologies() {
+        assert_eq!(
+            Topology::Pipeline.targets(3),
+            Ok(vec![vec![1], vec![2], vec![]])
+        );
+        assert_eq!(
+            Topology::Ring.targets(3),
+            Ok(vec![vec![1], vec![2], vec![0]])
+        );
+        assert_eq!(
+            Topology::FullyConnected.targets(3),
+            Ok(vec![vec![1, 2], vec![0, 2], vec![0, 1]])
+        );
+
+        // 0 fans out to 1 and 2, which both fault on their first instruction
+        let mut nodes = machines(&INCREMENT, &[&[1]]);
+        nodes.extend(machines(&[42], &[&[], &[]]));
+        let topology = Topology::Custom(vec![vec![1, 2], vec![], vec![]]);
+        let report = run_network(nodes.clone(), &topology).unwrap();
+
+        assert_eq!(report.machines[0].outcome, Outcome::Deadlocked);
+        assert_eq!(report.machines[0].outputs, vec![2]);
+        for m in &report.machines[1..] {
+            assert_eq!(
+                m.outcome,
+                Outcome::Faulted(IntcodeError::UnknownOpcode { pc: 0, opcode: 42 })
+            );
+        }
+
+        let wrong = [
+            (
+                Topology::Custom(vec![vec![1], vec![]]),
+                TopologyError::Lists {
+                    machines: 3,
+                    lists: 2,
+                },
+            ),
+            (
+                Topology::Custom(vec![vec![1], vec![], vec![0, 3]]),
+                TopologyError::NoSuchTarget {
+                    machine: 2,
+                    target: 3,
+                },
+            ),
+        ];
+        for (topology, error) in wrong {
+            assert_eq!(run_network(nodes.clone(), &topology), Err(error));
+        }
+    }
+}
@@ -0,0 +1,281 @@
+use crate::trace::{Event, Tracer};
+use crate::{Computer, Instruction, Memory, Opcode, Word};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+
+/// Tracer counting how often each instruction runs and where control flow goes
+#[derive(Debug, Clone, Default)]
+pub struct Profiler {
+    total: u64,
+    addresses: BTreeMap<usize, (Instruction, u64)>,
+    opcodes: HashMap<Opcode, u64>,
+    jumps: BTreeMap<(usize, usize), u64>,
+}
+
+/// A straight run of instructions only ever entered at the top
+#[derive(Debug, Clone, PartialEq)]
+pub struct Block {
+    pub start: usize,
+    pub end: usize,
+    pub instructions: usize,
+    pub count: u64,
+}
+
+impl Block {
+    /// Instructions executed inside the block
+    pub fn weight(&self) -> u64 {
+        self.count * self.instructions as u64
+    }
+}
+
+impl Profiler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    pub fn count(&self, address: usize) -> u64 {
+        self.addresses.get(&address).map_or(0, |&(_, count)| count)
+    }
+
+    pub fn opcode_count(&self, opcode: Opcode) -> u64 {
+        self.opcodes.get(&opcode).copied().unwrap_or(0)
+    }
+
+    /// Taken jumps as `(from, to)` with how often each was taken
+    pub fn jumps(&self) -> impl Iterator<Item = (&(usize, usize), &u64)> {
+        self.jumps.iter()
+    }
+
+    /// The `n` most executed addresses, busiest first
+    pub fn hottest(&self, n: usize) -> Vec<(usize, Instruction, u64)> {
+        let mut all: Vec<_> = self
+            .addresses
+            .iter()
+            .map(|(&address, &(instruction, count))| (address, instruction, count))
+            .collect();
+        all.sort_by(|a, b| b.2.cmp(&a.2).then(a.0.cmp(&b.0)));
+        all.truncate(n);
+        all
+    }
+
+    /// Split the executed instructions into basic blocks, heaviest first
+    pub fn blocks(&self) -> Vec<Block> {
+        let targets: BTreeSet<_> = self.jumps.keys().map(|&(_, to)| to).collect();
+        let mut blocks: Vec<Block> = Vec::new();
+        let mut closed = true;
+
+        for (&address, &(instruction, count)) in &self.addresses {
+            match blocks.last_mut() {
+                Some(block)
+                    if !closed
+                        && block.end == address
+                        && block.count == count
+                        && !targets.contains(&address) =>
+                {
+                    block.end += instruction.size();
+                    block.instructions += 1;
+                }
+                _ => blocks.push(Block {
+                    start: address,
+                    end: address + instruction.size(),
+                    instructions: 1,
+                    count,
+                }),
+            }
+
+            closed = matches!(
+                instruction.opcode,
+                Opcode::JumpIfTrue | Opcode::JumpIfFalse | Opcode::Halt
+            );
+        }
+
+        blocks.sort_by(|a, b| b.weight().cmp(&a.weight()).then(a.start.cmp(&b.start)));
+        blocks
+    }
+
+    pub(crate) fn instruction(&mut self, pc: usize, instruction: Instruction) {
+        self.total += 1;
+        self.addresses.entry(pc).or_insert((instruction, 0)).1 += 1;
+        *self.opcodes.entry(instruction.opcode).or_insert(0) += 1;
+    }
+
+    pub(crate) fn jump(&mut self, from: usize, to: usize) {
+        *self.jumps.entry((from, to)).or_insert(0) += 1;
+    }
+
+    /// Report of the `top` hottest instructions and blocks
+    pub fn report(&self, top: usize) -> Report<'_> {
+        Report {
+            profiler: self,
+            top,
+        }
+    }
+}
+
+pub struct Report<'a> {
+    profiler: &'a Profiler,
+    top: usize,
+}
+
+impl fmt::Display for Report<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let p = self.profiler;
+        let percent = |count: u64| 100.0 * count as f64 / p.total.max(1) as f64;
+
+        writeln!(f, "{} instructions", p.total)?;
+
+        writeln!(f, "\nopcodes")?;
+        let mut opcodes: Vec<_> = p.opcodes.iter().collect();
+        opcodes.sort_by(|a, b| b.1.cmp(a.1).then(a.0.code().cmp(&b.0.code())));
+        for (opcode, &count) in opcodes {
+            writeln!(
+                f,
+                "  {:<5}{:>10} {:>6.2}%",
+                opcode.mnemonic(),
+                count,
+                percent(count)
+            )?;
+        }
+
+        writeln!(f, "\nhottest instructions")?;
+        for (address, instruction, count) in p.hottest(self.top) {
+            writeln!(
+                f,
+                "  {:>5}  {:<5}{:>10} {:>6.2}%",
+                address,
+                instruction.opcode.mnemonic(),
+                count,
+                percent(count)
+            )?;
+        }
+
+        writeln!(f, "\nhottest blocks")?;
+        for block in p.blocks().iter().take(self.top) {
+            writeln!(
+                f,
+                "  {:>5}..{:<5} {:>3} instructions x {:>8} {:>6.2}%",
+                block.start,
+                block.end,
+                block.instructions,
+                block.count,
+                percent(block.weight())
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<W> Tracer<W> for Profiler {
+    fn trace(&mut self, event: &Event<W>) {
+        match event {
+            Event::Instruction {
+                pc, instruction, ..
+            } => self.instruction(*pc, *instruction),
+            Event::Jump { from, to } => self.jump(*from, *to),
+            _ => (),
+        }
+    }
+}
+
+impl<W: Word, M: Memory<W>> Computer<W, M> {
+    /// Profile every instruction from now on, without passing a tracer to each run
+    pub fn profile(&mut self) {
+        self.profiler = Some(Box::new(Profiler::new()));
+    }
+
+    pub fn profiler(&self) -> Option<&Profiler> {
+        self.profiler.as_deref()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::asm::assemble;
+    use crate::Computer;
+
+    #[test]
+    fn profile() {
+        // count down from 3
+        let program = assemble(
+            "
+                    in   [n]
+            loop:   add  [n], #-1, [n]
+                    out  [n]
+                    jt   [n], #loop
+                    hlt
+            n:      data 0
+            ",
+        )
+        .unwrap();
+
+        let mut c = Computer::new(&program);
+        c.input_add(3);
+        let mut profiler = Profiler::new();
+        while c.try_run_traced(&mut profiler).unwrap() != crate::ComputerStatus::Halt {}
+
+        assert_eq!(profiler.total(), 1 + 3 * 3 + 1);
+        assert_eq!(profiler.count(2), 3);
+        assert_eq!(profiler.opcode_count(Opcode::Add), 3);
+        assert_eq!(profiler.jumps().collect::<Vec<_>>(), vec![(&(8, 2), &2)]);
+        assert_eq!(
+            profiler.blocks(),
+            vec![
+                Block {
+                    start: 2,
+                    end: 11,
+                    instructions: 3,
+                    count: 3
+                },
+                Block {
+                    start: 0,
+                    end: 2,
+                    instructions: 1,
+                    count: 1
+                },
+                Block {
+                    start: 11,
+                    end: 12,
+                    instructions: 1,
+                    count: 1
+                },
+            ]
+        );
+
+        assert_eq!(
+            profiler.report(2).to_string(),
+            "11 instructions
+
+opcodes
+  ADD           3  27.27%
+  OUT           3  27.27%
+  JT            3  27.27%
+  IN            1   9.09%
+  HLT           1   9.09%
+
+hottest instructions
+      2  ADD           3  27.27%
+      6  OUT           3  27.27%
+
+hottest blocks
+      2..11      3 instructions x        3  81.82%
+      0..2       1 instructions x        1   9.09%
+"
+        );
+
+        // the same counts without a tracer
+        let mut c = Computer::new(&program);
+        c.input_add(3);
+        c.profile();
+        while c.run() != crate::ComputerStatus::Halt {}
+        assert_eq!(
+            c.profiler().unwrap().report(2).to_string(),
+            profiler.report(2).to_string()
+        );
+    }
+}
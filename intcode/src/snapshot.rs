@@ -0,0 +1,267 @@
+use crate::{Computer, Intcode, Memory, Word};
+use bincode::Options;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::convert::TryFrom;
+use std::fmt;
+
+/// Most words loading a snapshot may allocate, far more than any real program needs
+const MAX_GROWTH: usize = 1 << 24;
+
+/// Everything needed to resume a `Computer` exactly where it left off
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot<W = Intcode> {
+    /// Allocated memory as `(start address, words)` runs
+    pub memory: Vec<(usize, Vec<W>)>,
+    pub program_counter: usize,
+    pub base_offset: isize,
+    pub input: VecDeque<W>,
+    pub output: VecDeque<W>,
+    pub halted: bool,
+}
+
+/// A snapshot that cannot be loaded, such as a corrupt or hostile file
+#[derive(Debug, Clone, PartialEq)]
+pub enum SnapshotError {
+    /// A memory region runs past the last address
+    Overflow { start: usize, len: usize },
+    /// Loading it would allocate more than `MAX_GROWTH` words
+    TooLarge { start: usize, len: usize },
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::Overflow { start, len } => {
+                write!(f, "{} words at {} run past the last address", len, start)
+            }
+            SnapshotError::TooLarge { start, len } => {
+                write!(f, "{} words at {} need too much memory", len, start)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+fn binary() -> impl Options {
+    // varints keep the mostly small memory words to a byte or two
+    bincode::DefaultOptions::new()
+}
+
+impl<W: Serialize + DeserializeOwned> Snapshot<W> {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("snapshot serializes to JSON")
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        binary()
+            .serialize(self)
+            .expect("snapshot serializes to bytes")
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, bincode::Error> {
+        binary().deserialize(bytes)
+    }
+}
+
+impl<W: Word, M: Memory<W>> Computer<W, M> {
+    pub fn snapshot(&self) -> Snapshot<W> {
+        Snapshot {
+            memory: self
+                .memory
+                .regions()
+                .into_iter()
+                .map(|(start, words)| (start, words.to_vec()))
+                .collect(),
+            program_counter: self.program_counter(),
+            base_offset: self.base_offset,
+            input: self.input.clone(),
+            output: self.output.clone(),
+            halted: self.halted,
+        }
+    }
+
+    /// Replace the whole state with `snapshot`, or leave it untouched if it cannot be loaded
+    pub fn restore(&mut self, snapshot: Snapshot<W>) -> Result<(), SnapshotError>
+    where
+        M: Default,
+    {
+        let restored = Computer::try_from(snapshot)?;
+        let checked = self.checked;
+        let strict = self.watch.as_ref().map(|watch| watch.strict());
+        let recording = self.history.is_some();
+        // counts of what already ran stay true
+        let profiler = self.profiler.take();
+        let coverage = self.coverage.take();
+        *self = restored;
+        self.checked = checked;
+        self.profiler = profiler;
+        self.coverage = coverage;
+        // what ran before no longer describes the restored memory
+        if let Some(strict) = strict {
+            self.watch_code(strict);
+        }
+        if recording {
+            self.record_history();
+        }
+        Ok(())
+    }
+}
+
+impl<W: Word, M: Memory<W> + Default> TryFrom<Snapshot<W>> for Computer<W, M> {
+    type Error = SnapshotError;
+
+    fn try_from(snapshot: Snapshot<W>) -> Result<Self, SnapshotError> {
+        let mut memory = M::default();
+        let mut grown = 0;
+        for (start, words) in snapshot.memory {
+            let len = words.len();
+            if start.checked_add(len).is_none() {
+                return Err(SnapshotError::Overflow { start, len });
+            }
+            grown += memory.growth(start, len);
+            if grown > MAX_GROWTH {
+                return Err(SnapshotError::TooLarge { start, len });
+            }
+            for (offset, value) in words.into_iter().enumerate() {
+                memory.write(start + offset, value);
+            }
+        }
+
+        Ok(Self {
+            input: snapshot.input,
+            output: snapshot.output,
+            memory,
+            program_counter: Cell::new(snapshot.program_counter),
+            current_instruction: snapshot.program_counter,
+            base_offset: snapshot.base_offset,
+            halted: snapshot.halted,
+            checked: false,
+            watch: None,
+            history: None,
+            profiler: None,
+            coverage: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{ComputerStatus, PagedMemory};
+
+    // echoes input plus one, forever, using the relative base
+    const ECHO: [Intcode; 14] = [109, 1, 203, 0, 1201, 0, 1, 13, 4, 13, 1105, 1, 2, 0];
+
+    fn paused() -> Computer {
+        let mut c = Computer::new(&ECHO);
+        c.input_add_all([10, 20].iter());
+        assert_eq!(c.run(), ComputerStatus::ReturnedValue);
+        c
+    }
+
+    #[test]
+    fn round_trip() {
+        let snapshot = paused().snapshot();
+        assert_eq!(snapshot.program_counter, 10);
+        assert_eq!(snapshot.base_offset, 1);
+        assert_eq!(snapshot.input, vec![20]);
+        assert_eq!(snapshot.output, vec![11]);
+
+        assert_eq!(Snapshot::from_json(&snapshot.to_json()).unwrap(), snapshot);
+        assert_eq!(
+            Snapshot::from_bytes(&snapshot.to_bytes()).unwrap(),
+            snapshot
+        );
+        assert!(snapshot.to_bytes().len() < snapshot.to_json().len());
+
+        assert!(Snapshot::<Intcode>::from_json("{\"memory\":[]}").is_err());
+        assert!(Snapshot::<Intcode>::from_bytes(&[1, 2, 3]).is_err());
+    }
+
+    #[test]
+    fn resume() {
+        let mut original = paused();
+        let mut restored: Computer =
+            Computer::try_from(Snapshot::from_bytes(&original.snapshot().to_bytes()).unwrap())
+                .unwrap();
+
+        for c in [&mut original, &mut restored].iter_mut() {
+            assert_eq!(c.run(), ComputerStatus::ReturnedValue);
+            c.input_add(30);
+            assert_eq!(c.run(), ComputerStatus::ReturnedValue);
+            assert_eq!(c.run(), ComputerStatus::WaitingForInput);
+        }
+        assert_eq!(original.snapshot(), restored.snapshot());
+        assert_eq!(restored.output, vec![11, 21, 31]);
+    }
+
+    #[test]
+    fn fork() {
+        let mut c = paused();
+        let saved = c.snapshot();
+        let mut fork = c.clone();
+
+        fork.input_add(100);
+        while fork.run() != ComputerStatus::WaitingForInput {}
+        assert_eq!(fork.output, vec![11, 21, 101]);
+        assert_eq!(c.snapshot(), saved);
+
+        c.run();
+        c.restore(saved.clone()).unwrap();
+        assert_eq!(c.snapshot(), saved);
+    }
+
+    #[test]
+    fn paged() {
+        let mut c = Computer::with_memory(PagedMemory::from(&ECHO[..]));
+        c.input_add_all([10, 20].iter());
+        c.run();
+
+        let snapshot = c.snapshot();
+        assert_eq!(snapshot.memory.len(), 1);
+        assert_eq!(snapshot.memory[0].1[..ECHO.len()], paused().memory()[..]);
+
+        let mut restored: Computer<Intcode, PagedMemory> =
+            Computer::try_from(snapshot.clone()).unwrap();
+        assert_eq!(restored.snapshot(), snapshot);
+        restored.run();
+        assert_eq!(restored.output, vec![11, 21]);
+    }
+
+    #[test]
+    fn hostile() {
+        let mut snapshot = paused().snapshot();
+        snapshot.memory.push((usize::MAX, vec![1, 2]));
+        assert_eq!(
+            Computer::<Intcode>::try_from(snapshot.clone()).err(),
+            Some(SnapshotError::Overflow {
+                start: usize::MAX,
+                len: 2
+            })
+        );
+
+        let far = 1 << 40;
+        snapshot.memory.pop();
+        snapshot.memory.push((far, vec![1]));
+        assert_eq!(
+            Computer::<Intcode>::try_from(snapshot.clone()).err(),
+            Some(SnapshotError::TooLarge { start: far, len: 1 })
+        );
+        let paged: Computer<Intcode, PagedMemory> = Computer::try_from(snapshot.clone()).unwrap();
+        assert_eq!(paged.memory().read(far), 1);
+
+        // a failed restore keeps the machine as it was
+        let mut c = paused();
+        let before = c.snapshot();
+        assert!(c.restore(snapshot).is_err());
+        assert_eq!(c.snapshot(), before);
+    }
+}
@@ -0,0 +1,689 @@
+use crate::{Instruction, Intcode, IntcodeError, Opcode, ParamMode};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::rc::Rc;
+
+/// Largest address a path may write to
+const MEMORY: usize = 1 << 20;
+
+/// An unknown: the nth value read by `IN`, or the initial contents of a memory cell
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Var {
+    Input(usize),
+    Cell(usize),
+}
+
+impl fmt::Display for Var {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Var::Input(n) => write!(f, "in{}", n),
+            Var::Cell(address) => write!(f, "m{}", address),
+        }
+    }
+}
+
+/// `constant + sum(coefficient * var)`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Linear {
+    pub terms: BTreeMap<Var, Intcode>,
+    pub constant: Intcode,
+}
+
+impl Linear {
+    fn add(&self, other: &Linear) -> Option<Linear> {
+        let mut sum = self.clone();
+        sum.constant = sum.constant.checked_add(other.constant)?;
+        for (&var, &coefficient) in &other.terms {
+            let term = sum.terms.entry(var).or_insert(0);
+            *term = term.checked_add(coefficient)?;
+            if *term == 0 {
+                sum.terms.remove(&var);
+            }
+        }
+        Some(sum)
+    }
+
+    fn scale(&self, factor: Intcode) -> Option<Linear> {
+        let mut terms = BTreeMap::new();
+        if factor != 0 {
+            for (&var, &coefficient) in &self.terms {
+                terms.insert(var, coefficient.checked_mul(factor)?);
+            }
+        }
+        Some(Linear {
+            terms,
+            constant: self.constant.checked_mul(factor)?,
+        })
+    }
+
+    pub fn eval(&self, values: &BTreeMap<Var, Intcode>) -> Option<Intcode> {
+        self.terms
+            .iter()
+            .try_fold(self.constant, |sum, (var, &coefficient)| {
+                sum.checked_add(coefficient.checked_mul(*values.get(var)?)?)
+            })
+    }
+
+    /// Values for `vars`, each within `range`, making the form equal `target`. All but one of
+    /// the variables are searched exhaustively, so this is meant for a handful of them.
+    pub fn solve(
+        &self,
+        target: Intcode,
+        vars: &[Var],
+        range: RangeInclusive<Intcode>,
+    ) -> Option<Vec<Intcode>> {
+        if range.is_empty() || self.terms.keys().any(|var| !vars.contains(var)) {
+            return None;
+        }
+        let solved = vars.iter().rposition(|var| self.terms.contains_key(var));
+        let mut values = vec![*range.start(); vars.len()];
+
+        loop {
+            let mut assignment: BTreeMap<Var, Intcode> =
+                vars.iter().copied().zip(values.iter().copied()).collect();
+            match solved {
+                None if self.constant == target => return Some(values),
+                None => return None,
+                Some(idx) => {
+                    assignment.remove(&vars[idx]);
+                    let coefficient = self.terms[&vars[idx]];
+                    let mut rest = self.clone();
+                    rest.terms.remove(&vars[idx]);
+                    let rest = rest.eval(&assignment).and_then(|r| target.checked_sub(r));
+                    if let Some(rest) = rest {
+                        let value = rest.checked_div(coefficient);
+                        let exact = rest.checked_rem(coefficient) == Some(0);
+                        match value {
+                            Some(value) if exact && range.contains(&value) => {
+                                values[idx] = value;
+                                return Some(values);
+                            }
+                            _ => (),
+                        }
+                    }
+                }
+            }
+
+            // count through the other variables like an odometer
+            let mut carry = true;
+            for (idx, value) in values.iter_mut().enumerate() {
+                if Some(idx) == solved || !carry {
+                    continue;
+                }
+                if *value < *range.end() {
+                    *value += 1;
+                    carry = false;
+                } else {
+                    *value = *range.start();
+                }
+            }
+            if carry {
+                return None;
+            }
+        }
+    }
+}
+
+impl fmt::Display for Linear {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut first = true;
+        for (var, &coefficient) in &self.terms {
+            let sign = match (first, coefficient < 0) {
+                (true, true) => "-",
+                (true, false) => "",
+                (false, true) => " - ",
+                (false, false) => " + ",
+            };
+            match coefficient.abs() {
+                1 => write!(f, "{}{}", sign, var)?,
+                n => write!(f, "{}{}*{}", sign, n, var)?,
+            }
+            first = false;
+        }
+        match (first, self.constant) {
+            (true, n) => write!(f, "{}", n),
+            (false, 0) => Ok(()),
+            (false, n) if n < 0 => write!(f, " - {}", -n),
+            (false, n) => write!(f, " + {}", n),
+        }
+    }
+}
+
+/// A value computed from unknowns. Sums and multiples are kept linear while they can be.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    Linear(Linear),
+    Add(Rc<Expr>, Rc<Expr>),
+    Mul(Rc<Expr>, Rc<Expr>),
+    Lt(Rc<Expr>, Rc<Expr>),
+    Eq(Rc<Expr>, Rc<Expr>),
+    /// Memory read through an address that is not known
+    Load(Rc<Expr>),
+}
+
+impl Expr {
+    pub fn constant(value: Intcode) -> Self {
+        Expr::Linear(Linear {
+            terms: BTreeMap::new(),
+            constant: value,
+        })
+    }
+
+    pub fn var(var: Var) -> Self {
+        Expr::Linear(Linear {
+            terms: std::iter::once((var, 1)).collect(),
+            constant: 0,
+        })
+    }
+
+    pub fn linear(&self) -> Option<&Linear> {
+        match self {
+            Expr::Linear(linear) => Some(linear),
+            _ => None,
+        }
+    }
+
+    pub fn value(&self) -> Option<Intcode> {
+        self.linear()
+            .filter(|linear| linear.terms.is_empty())
+            .map(|linear| linear.constant)
+    }
+
+    /// None when constants overflow
+    fn add(a: Expr, b: Expr) -> Option<Expr> {
+        Some(match (a, b) {
+            (Expr::Linear(a), Expr::Linear(b)) => Expr::Linear(a.add(&b)?),
+            (a, b) if a.value() == Some(0) => b,
+            (a, b) if b.value() == Some(0) => a,
+            (a, b) => Expr::Add(Rc::new(a), Rc::new(b)),
+        })
+    }
+
+    fn mul(a: Expr, b: Expr) -> Option<Expr> {
+        let (factor, other) = match (a.value(), b.value()) {
+            (Some(n), _) => (n, b),
+            (_, Some(n)) => (n, a),
+            _ => return Some(Expr::Mul(Rc::new(a), Rc::new(b))),
+        };
+        Some(match other {
+            Expr::Linear(linear) => Expr::Linear(linear.scale(factor)?),
+            _ if factor == 0 => Expr::constant(0),
+            _ if factor == 1 => other,
+            _ => Expr::Mul(Rc::new(Expr::constant(factor)), Rc::new(other)),
+        })
+    }
+
+    fn compare(a: Expr, b: Expr, equals: bool) -> Expr {
+        match (a.value(), b.value()) {
+            (Some(x), Some(y)) if equals => Expr::constant((x == y) as Intcode),
+            (Some(x), Some(y)) => Expr::constant((x < y) as Intcode),
+            _ if equals && a == b => Expr::constant(1),
+            _ if equals => Expr::Eq(Rc::new(a), Rc::new(b)),
+            _ => Expr::Lt(Rc::new(a), Rc::new(b)),
+        }
+    }
+
+    /// Whether it needs brackets inside another expression
+    fn compound(&self) -> bool {
+        match self {
+            Expr::Linear(linear) => {
+                linear.terms.len() + (linear.constant != 0) as usize > 1
+                    || linear.terms.values().any(|&c| c != 1)
+            }
+            Expr::Load(_) => false,
+            _ => true,
+        }
+    }
+}
+
+/// `e` as an operand, bracketed if it would bind less tightly than a sum or a comparison
+fn operand(e: &Expr, comparison: bool) -> String {
+    let bracket = match e {
+        Expr::Lt(_, _) | Expr::Eq(_, _) => true,
+        _ => !comparison && e.compound(),
+    };
+    if bracket {
+        format!("({})", e)
+    } else {
+        e.to_string()
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Linear(linear) => write!(f, "{}", linear),
+            Expr::Add(a, b) => write!(f, "{} + {}", operand(a, false), operand(b, false)),
+            Expr::Mul(a, b) => write!(f, "{} * {}", operand(a, false), operand(b, false)),
+            Expr::Lt(a, b) => write!(f, "{} < {}", operand(a, true), operand(b, true)),
+            Expr::Eq(a, b) => write!(f, "{} == {}", operand(a, true), operand(b, true)),
+            Expr::Load(address) => write!(f, "mem[{}]", address),
+        }
+    }
+}
+
+/// A branch decision: `condition` was non-zero if `holds`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Constraint {
+    pub condition: Expr,
+    pub holds: bool,
+}
+
+impl fmt::Display for Constraint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.condition, self.holds) {
+            (Expr::Lt(_, _), true) | (Expr::Eq(_, _), true) => write!(f, "{}", self.condition),
+            (Expr::Lt(a, b), false) => write!(f, "{} >= {}", operand(a, true), operand(b, true)),
+            (Expr::Eq(a, b), false) => write!(f, "{} != {}", operand(a, true), operand(b, true)),
+            (condition, true) => write!(f, "{} != 0", operand(condition, true)),
+            (condition, false) => write!(f, "{} == 0", operand(condition, true)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum End {
+    Halted,
+    Fault(IntcodeError),
+    /// Something that has to be concrete is not: an opcode, a jump target, a written
+    /// address or a relative base adjustment
+    Unsupported {
+        pc: usize,
+    },
+    StepLimit,
+}
+
+/// One way through the program
+#[derive(Debug, Clone, PartialEq)]
+pub struct Path {
+    pub constraints: Vec<Constraint>,
+    pub outputs: Vec<Expr>,
+    pub inputs: usize,
+    pub end: End,
+    memory: Vec<Expr>,
+}
+
+impl Path {
+    pub fn read(&self, address: usize) -> Expr {
+        self.memory
+            .get(address)
+            .cloned()
+            .unwrap_or_else(|| Expr::constant(0))
+    }
+}
+
+enum Step {
+    Next,
+    /// Jumps to `target` if `condition` is non-zero, or zero for `JF`
+    Branch {
+        condition: Expr,
+        when: bool,
+        target: Expr,
+    },
+    End(End),
+}
+
+#[derive(Clone)]
+struct State {
+    memory: Vec<Expr>,
+    pc: usize,
+    current: usize,
+    base: Intcode,
+    inputs: usize,
+    outputs: Vec<Expr>,
+    constraints: Vec<Constraint>,
+    steps: usize,
+}
+
+impl State {
+    fn read(&self, address: usize) -> Expr {
+        self.memory
+            .get(address)
+            .cloned()
+            .unwrap_or_else(|| Expr::constant(0))
+    }
+
+    fn unsupported(&self) -> End {
+        End::Unsupported { pc: self.current }
+    }
+
+    fn overflow(&self) -> End {
+        End::Fault(IntcodeError::Overflow { pc: self.current })
+    }
+
+    fn operand(&self, ins: &Instruction, idx: usize) -> Result<Expr, End> {
+        let word = self.read(self.current + 1 + idx);
+        let address = match ins.modes[idx] {
+            ParamMode::Immediate => return Ok(word),
+            ParamMode::Position => word,
+            ParamMode::Relative => {
+                Expr::add(word, Expr::constant(self.base)).ok_or_else(|| self.overflow())?
+            }
+        };
+
+        Ok(match address.value() {
+            Some(address) if address < 0 => Expr::constant(0),
+            Some(address) => self.read(address as usize),
+            None => Expr::Load(Rc::new(address)),
+        })
+    }
+
+    fn write(&mut self, ins: &Instruction, idx: usize, value: Expr) -> Result<(), End> {
+        let word = self.read(self.current + 1 + idx);
+        let address = match ins.modes[idx] {
+            ParamMode::Immediate => {
+                return Err(End::Fault(IntcodeError::ImmediateWrite {
+                    pc: self.current,
+                }))
+            }
+            ParamMode::Position => word.value().map(Some),
+            ParamMode::Relative => word.value().map(|w| w.checked_add(self.base)),
+        };
+        let address = match address {
+            Some(Some(address)) => address,
+            Some(None) => return Err(self.overflow()),
+            None => return Err(self.unsupported()),
+        };
+
+        if address < 0 {
+            return Err(End::Fault(IntcodeError::NegativeAddress {
+                pc: self.current,
+                address,
+            }));
+        }
+        let address = address as usize;
+        if address >= MEMORY {
+            return Err(self.unsupported());
+        }
+        if address >= self.memory.len() {
+            self.memory.resize(address + 1, Expr::constant(0));
+        }
+        self.memory[address] = value;
+        Ok(())
+    }
+
+    fn jump(&mut self, target: &Expr) -> Result<(), End> {
+        match target.value() {
+            Some(target) if target < 0 => Err(End::Fault(IntcodeError::NegativeAddress {
+                pc: self.current,
+                address: target,
+            })),
+            Some(target) => {
+                self.pc = target as usize;
+                Ok(())
+            }
+            None => Err(self.unsupported()),
+        }
+    }
+
+    fn step(&mut self) -> Result<Step, End> {
+        self.current = self.pc;
+        let word = self
+            .read(self.pc)
+            .value()
+            .ok_or_else(|| self.unsupported())?;
+        let ins = Instruction::decode(self.pc, word).map_err(End::Fault)?;
+        self.pc += ins.size();
+        self.steps += 1;
+
+        match ins.opcode {
+            Opcode::Add | Opcode::Mul | Opcode::LessThan | Opcode::Equals => {
+                let a = self.operand(&ins, 0)?;
+                let b = self.operand(&ins, 1)?;
+                let value = match ins.opcode {
+                    Opcode::Add => Expr::add(a, b).ok_or_else(|| self.overflow())?,
+                    Opcode::Mul => Expr::mul(a, b).ok_or_else(|| self.overflow())?,
+                    opcode => Expr::compare(a, b, opcode == Opcode::Equals),
+                };
+                self.write(&ins, 2, value)?;
+            }
+            Opcode::Input => {
+                let value = Expr::var(Var::Input(self.inputs));
+                self.inputs += 1;
+                self.write(&ins, 0, value)?;
+            }
+            Opcode::Output => {
+                let value = self.operand(&ins, 0)?;
+                self.outputs.push(value);
+            }
+            Opcode::JumpIfTrue | Opcode::JumpIfFalse => {
+                return Ok(Step::Branch {
+                    condition: self.operand(&ins, 0)?,
+                    when: ins.opcode == Opcode::JumpIfTrue,
+                    target: self.operand(&ins, 1)?,
+                })
+            }
+            Opcode::AdjustBase => {
+                let by = self
+                    .operand(&ins, 0)?
+                    .value()
+                    .ok_or_else(|| self.unsupported())?;
+                self.base = self.base.checked_add(by).ok_or_else(|| self.overflow())?;
+            }
+            Opcode::Halt => {
+                self.pc = self.current;
+                return Ok(Step::End(End::Halted));
+            }
+        }
+        Ok(Step::Next)
+    }
+
+    fn finish(self, end: End) -> Path {
+        Path {
+            constraints: self.constraints,
+            outputs: self.outputs,
+            inputs: self.inputs,
+            end,
+            memory: self.memory,
+        }
+    }
+}
+
+/// Runs a program on unknown inputs, following both sides of every branch that depends on
+/// them
+pub struct Symbolic {
+    memory: Vec<Expr>,
+    max_steps: usize,
+    max_paths: usize,
+}
+
+impl Symbolic {
+    pub fn new(program: &[Intcode]) -> Self {
+        Self {
+            memory: program.iter().map(|&word| Expr::constant(word)).collect(),
+            max_steps: 100_000,
+            max_paths: 1_000,
+        }
+    }
+
+    /// Treat the initial contents of `address` as unknown
+    pub fn unknown(&mut self, address: usize) {
+        if address >= self.memory.len() {
+            self.memory.resize(address + 1, Expr::constant(0));
+        }
+        self.memory[address] = Expr::var(Var::Cell(address));
+    }
+
+    /// Stop a path after `steps` instructions, and exploring after `paths` paths
+    pub fn set_limits(&mut self, steps: usize, paths: usize) {
+        self.max_steps = steps;
+        self.max_paths = paths;
+    }
+
+    /// Every path through the program, up to the limits, taken branches first
+    pub fn explore(&self) -> Vec<Path> {
+        let mut paths = Vec::new();
+        let mut work = vec![State {
+            memory: self.memory.clone(),
+            pc: 0,
+            current: 0,
+            base: 0,
+            inputs: 0,
+            outputs: Vec::new(),
+            constraints: Vec::new(),
+            steps: 0,
+        }];
+
+        while let Some(mut state) = work.pop() {
+            if paths.len() >= self.max_paths {
+                break;
+            }
+
+            let end = loop {
+                if state.steps >= self.max_steps {
+                    break End::StepLimit;
+                }
+                let (condition, when, target) = match state.step() {
+                    Ok(Step::Next) => continue,
+                    Ok(Step::End(end)) | Err(end) => break end,
+                    Ok(Step::Branch {
+                        condition,
+                        when,
+                        target,
+                    }) => (condition, when, target),
+                };
+
+                // decided by a constant, or by the same condition earlier on this path
+                let known = condition.value().map(|v| v != 0).or_else(|| {
+                    state
+                        .constraints
+                        .iter()
+                        .find(|c| c.condition == condition)
+                        .map(|c| c.holds)
+                });
+                match known {
+                    Some(holds) if holds == when => match state.jump(&target) {
+                        Ok(()) => continue,
+                        Err(end) => break end,
+                    },
+                    Some(_) => continue,
+                    None => {
+                        let mut skipped = state.clone();
+                        skipped.constraints.push(Constraint {
+                            condition: condition.clone(),
+                            holds: !when,
+                        });
+                        work.push(skipped);
+
+                        state.constraints.push(Constraint {
+                            condition,
+                            holds: when,
+                        });
+                        if let Err(end) = state.jump(&target) {
+                            break end;
+                        }
+                    }
+                }
+            };
+            paths.push(state.finish(end));
+        }
+
+        paths
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Computer;
+
+    #[test]
+    fn sample_4() {
+        let program = [
+            3, 21, 1008, 21, 8, 20, 1005, 20, 22, 107, 8, 21, 20, 1006, 20, 31, 1106, 0, 36, 98, 0,
+            0, 1002, 21, 125, 20, 4, 20, 1105, 1, 46, 104, 999, 1105, 1, 46, 1101, 1000, 1, 20, 4,
+            20, 1105, 1, 46, 98, 99,
+        ];
+        let paths = Symbolic::new(&program).explore();
+
+        let summary: Vec<_> = paths
+            .iter()
+            .map(|path| {
+                let constraints: Vec<_> = path.constraints.iter().map(|c| c.to_string()).collect();
+                let outputs: Vec<_> = path.outputs.iter().map(|o| o.to_string()).collect();
+                (constraints.join(", "), outputs.join(", "), path.end.clone())
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("in0 == 8".to_string(), "125*in0".to_string(), End::Halted),
+                (
+                    "in0 != 8, 8 >= in0".to_string(),
+                    "999".to_string(),
+                    End::Halted
+                ),
+                (
+                    "in0 != 8, 8 < in0".to_string(),
+                    "1001".to_string(),
+                    End::Halted
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn noun_verb() {
+        let path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../aoc_02/input.txt");
+        let program = crate::read_input(&mut std::fs::File::open(path).unwrap()).unwrap();
+
+        let mut symbolic = Symbolic::new(&program);
+        symbolic.unknown(1);
+        symbolic.unknown(2);
+        let paths = symbolic.explore();
+        assert_eq!(paths.len(), 1);
+        let result = paths[0].read(0);
+        let form = result.linear().expect("linear in noun and verb");
+
+        let vars = [Var::Cell(1), Var::Cell(2)];
+        let part_1: BTreeMap<_, _> = vars.iter().copied().zip(vec![12, 2]).collect();
+        assert_eq!(form.eval(&part_1), Some(10_566_835));
+
+        let solution = form.solve(19_690_720, &vars, 0..=99).unwrap();
+        let mut patched = program.clone();
+        patched[1] = solution[0];
+        patched[2] = solution[1];
+        let mut computer = Computer::new(&patched);
+        computer.run();
+        assert_eq!(computer.memory()[0], 19_690_720);
+    }
+
+    #[test]
+    fn solve_edges() {
+        let vars = [Var::Cell(1)];
+        let negated = Linear {
+            terms: vars.iter().map(|&var| (var, -1)).collect(),
+            constant: 0,
+        };
+        assert_eq!(
+            negated.solve(Intcode::MIN, &vars, Intcode::MIN..=Intcode::MAX),
+            None
+        );
+
+        let constant = Linear {
+            terms: BTreeMap::new(),
+            constant: 5,
+        };
+        assert_eq!(constant.solve(5, &vars, 0..=0), Some(vec![0]));
+        assert_eq!(constant.solve(5, &vars, RangeInclusive::new(3, 2)), None);
+    }
+
+    #[test]
+    fn unsupported() {
+        // writes to an address read from input
+        let program = [3, 5, 1101, 1, 1, 0, 99];
+        let paths = Symbolic::new(&program).explore();
+        assert_eq!(paths[0].end, End::Unsupported { pc: 2 });
+
+        // loops for as long as its input is positive
+        let program = [3, 20, 1001, 20, -1, 20, 1007, 20, 1, 21, 1006, 21, 2, 99];
+        let mut symbolic = Symbolic::new(&program);
+        symbolic.set_limits(10, 3);
+        let paths = symbolic.explore();
+        let ends: Vec<_> = paths.iter().map(|p| p.end.clone()).collect();
+        assert_eq!(ends, vec![End::StepLimit, End::StepLimit, End::Halted]);
+        let constraints: Vec<_> = paths[2].constraints.iter().map(|c| c.to_string()).collect();
+        assert_eq!(constraints, vec!["in0 - 1 >= 1", "in0 - 2 < 1"]);
+    }
+}
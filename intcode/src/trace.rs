@@ -0,0 +1,262 @@
+use crate::{Instruction, Intcode};
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::{self, Write};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Event<W = Intcode> {
+    /// An instruction about to execute. Operands hold the values read, except for a
+    /// written parameter, which holds the resolved address.
+    Instruction {
+        pc: usize,
+        instruction: Instruction,
+        operands: [W; 3],
+    },
+    Write {
+        address: usize,
+        old: W,
+        value: W,
+    },
+    Jump {
+        from: usize,
+        to: usize,
+    },
+    Input(W),
+    Output(W),
+}
+
+pub trait Tracer<W = Intcode> {
+    fn trace(&mut self, event: &Event<W>);
+
+    /// Lets the VM skip building events nobody listens to
+    fn enabled(&self) -> bool {
+        true
+    }
+}
+
+pub struct NoTrace;
+
+impl<W> Tracer<W> for NoTrace {
+    #[inline(always)]
+    fn trace(&mut self, _: &Event<W>) {}
+
+    #[inline(always)]
+    fn enabled(&self) -> bool {
+        false
+    }
+}
+
+impl<W, T: Tracer<W> + ?Sized> Tracer<W> for &mut T {
+    fn trace(&mut self, event: &Event<W>) {
+        (**self).trace(event)
+    }
+
+    fn enabled(&self) -> bool {
+        (**self).enabled()
+    }
+}
+
+impl<W: fmt::Display> fmt::Display for Event<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Event::Instruction {
+                pc,
+                instruction,
+                operands,
+            } => {
+                let opcode = instruction.opcode;
+                let count = opcode.params();
+                let reads = if opcode.writes() { count - 1 } else { count };
+
+                let values: Vec<_> = operands[..reads].iter().map(|o| o.to_string()).collect();
+                let text = format!("{:<5}{}", opcode.mnemonic(), values.join(", "));
+                write!(f, "{:>5}  {}", pc, text.trim_end())?;
+                if opcode.writes() {
+                    write!(f, " -> [{}]", operands[reads])?;
+                }
+                Ok(())
+            }
+            Event::Write {
+                address,
+                old,
+                value,
+            } => write!(f, "       [{}] = {} (was {})", address, value, old),
+            Event::Jump { from, to } => write!(f, "       jump {} -> {}", from, to),
+            Event::Input(v) => write!(f, "       input {}", v),
+            Event::Output(v) => write!(f, "       output {}", v),
+        }
+    }
+}
+
+/// Writes every event as a line of JSON
+pub struct JsonTracer<W: Write> {
+    out: W,
+    error: Option<io::Error>,
+}
+
+impl<W: Write> JsonTracer<W> {
+    pub fn new(out: W) -> Self {
+        Self { out, error: None }
+    }
+
+    /// Flush and hand back the writer, reporting the first write error if there was one
+    pub fn finish(mut self) -> io::Result<W> {
+        if let Some(e) = self.error {
+            return Err(e);
+        }
+        self.out.flush()?;
+        Ok(self.out)
+    }
+
+    fn write_event<V: fmt::Display>(&mut self, event: &Event<V>) -> io::Result<()> {
+        match event {
+            Event::Instruction {
+                pc,
+                instruction,
+                operands,
+            } => {
+                let count = instruction.opcode.params();
+                let modes: Vec<_> = instruction.modes[..count]
+                    .iter()
+                    .map(|m| m.code().to_string())
+                    .collect();
+                let operands: Vec<_> = operands[..count].iter().map(|o| o.to_string()).collect();
+
+                writeln!(
+                    self.out,
+                    r#"{{"event":"instruction","pc":{},"op":"{}","modes":[{}],"operands":[{}]}}"#,
+                    pc,
+                    instruction.opcode.mnemonic(),
+                    modes.join(","),
+                    operands.join(",")
+                )
+            }
+            Event::Write {
+                address,
+                old,
+                value,
+            } => writeln!(
+                self.out,
+                r#"{{"event":"write","address":{},"old":{},"value":{}}}"#,
+                address, old, value
+            ),
+            Event::Jump { from, to } => writeln!(
+                self.out,
+                r#"{{"event":"jump","from":{},"to":{}}}"#,
+                from, to
+            ),
+            Event::Input(v) => writeln!(self.out, r#"{{"event":"input","value":{}}}"#, v),
+            Event::Output(v) => writeln!(self.out, r#"{{"event":"output","value":{}}}"#, v),
+        }
+    }
+}
+
+impl<V: fmt::Display, W: Write> Tracer<V> for JsonTracer<W> {
+    fn trace(&mut self, event: &Event<V>) {
+        if self.error.is_none() {
+            if let Err(e) = self.write_event(event) {
+                self.error = Some(e);
+            }
+        }
+    }
+}
+
+/// Keeps the events belonging to the last `capacity` instructions
+pub struct RingTracer<W = Intcode> {
+    capacity: usize,
+    instructions: usize,
+    events: VecDeque<Event<W>>,
+}
+
+impl<W> RingTracer<W> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            instructions: 0,
+            events: VecDeque::new(),
+        }
+    }
+
+    pub fn events(&self) -> impl Iterator<Item = &Event<W>> {
+        self.events.iter()
+    }
+}
+
+impl<W: Clone> Tracer<W> for RingTracer<W> {
+    fn trace(&mut self, event: &Event<W>) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        if let Event::Instruction { .. } = event {
+            if self.instructions == self.capacity {
+                // drop the oldest instruction along with everything it caused
+                self.events.pop_front();
+                while !matches!(self.events.front(), Some(Event::Instruction { .. }) | None) {
+                    self.events.pop_front();
+                }
+            } else {
+                self.instructions += 1;
+            }
+        }
+
+        self.events.push_back(event.clone());
+    }
+}
+
+impl<W: fmt::Display> fmt::Display for RingTracer<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for event in &self.events {
+            writeln!(f, "{}", event)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Computer;
+
+    #[test]
+    fn json_lines() {
+        let mut c = Computer::new(&[3, 9, 1001, 9, 2, 9, 1005, 9, 10, 0, 104, 7, 99]);
+        c.input_add(5);
+
+        let mut tracer = JsonTracer::new(Vec::new());
+        c.try_run_traced(&mut tracer).unwrap();
+        let out = String::from_utf8(tracer.finish().unwrap()).unwrap();
+
+        assert_eq!(
+            out.lines().collect::<Vec<_>>(),
+            vec![
+                r#"{"event":"instruction","pc":0,"op":"IN","modes":[0],"operands":[9]}"#,
+                r#"{"event":"input","value":5}"#,
+                r#"{"event":"write","address":9,"old":0,"value":5}"#,
+                r#"{"event":"instruction","pc":2,"op":"ADD","modes":[0,1,0],"operands":[5,2,9]}"#,
+                r#"{"event":"write","address":9,"old":5,"value":7}"#,
+                r#"{"event":"instruction","pc":6,"op":"JT","modes":[0,1],"operands":[7,10]}"#,
+                r#"{"event":"jump","from":6,"to":10}"#,
+                r#"{"event":"instruction","pc":10,"op":"OUT","modes":[1],"operands":[7]}"#,
+                r#"{"event":"output","value":7}"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn ring() {
+        let mut c = Computer::new(&[3, 9, 1001, 9, 2, 9, 1005, 9, 10, 0, 104, 7, 99]);
+        c.input_add(5);
+
+        let mut tracer = RingTracer::new(2);
+        while c.try_run_traced(&mut tracer).unwrap() != crate::ComputerStatus::Halt {}
+
+        assert_eq!(
+            tracer.to_string(),
+            "   10  OUT  7
+       output 7
+   12  HLT
+"
+        );
+    }
+}
@@ -0,0 +1,152 @@
+use crate::IntcodeError;
+use std::collections::{HashMap, HashSet};
+
+/// A write into memory that is also executed as code
+#[derive(Debug, Clone, PartialEq)]
+pub struct CodeWrite<W> {
+    /// The instruction doing the write
+    pub pc: usize,
+    pub address: usize,
+    pub value: W,
+}
+
+/// Remembers which addresses were executed and which were written, to catch a write landing
+/// in code, whether the code ran before the write or only afterwards
+#[derive(Debug, Clone)]
+pub(crate) struct CodeWatch<W> {
+    strict: bool,
+    executed: HashSet<usize>,
+    written: HashMap<usize, CodeWrite<W>>,
+    log: Vec<CodeWrite<W>>,
+}
+
+impl<W: Clone> CodeWatch<W> {
+    pub(crate) fn new(strict: bool) -> Self {
+        Self {
+            strict,
+            executed: HashSet::new(),
+            written: HashMap::new(),
+            log: Vec::new(),
+        }
+    }
+
+    pub(crate) fn strict(&self) -> bool {
+        self.strict
+    }
+
+    pub(crate) fn log(&self) -> &[CodeWrite<W>] {
+        &self.log
+    }
+
+    /// Record the instruction at `pc`, faulting in strict mode if any of its words were written
+    pub(crate) fn execute(&mut self, pc: usize, size: usize) -> Result<(), IntcodeError> {
+        for address in pc..pc + size {
+            if self.strict && self.written.contains_key(&address) {
+                return Err(IntcodeError::SelfModifying { pc, address });
+            }
+            if let Some(write) = self.written.remove(&address) {
+                self.log.push(write);
+            }
+            self.executed.insert(address);
+        }
+        Ok(())
+    }
+
+    /// Record a write, faulting in strict mode if it lands on code that already ran
+    pub(crate) fn write(
+        &mut self,
+        pc: usize,
+        address: usize,
+        value: &W,
+    ) -> Result<(), IntcodeError> {
+        let write = CodeWrite {
+            pc,
+            address,
+            value: value.clone(),
+        };
+
+        if !self.executed.contains(&address) {
+            self.written.insert(address, write);
+        } else if self.strict {
+            return Err(IntcodeError::SelfModifying { pc, address });
+        } else {
+            self.log.push(write);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Computer, ComputerStatus};
+
+    #[test]
+    fn patched_later() {
+        // writes a halt into address 4, then runs it
+        let program = [1101, 0, 99, 4, 0];
+
+        let mut c = Computer::new(&program);
+        c.watch_code(false);
+        assert_eq!(c.try_run(), Ok(ComputerStatus::Halt));
+        assert_eq!(
+            c.code_writes(),
+            &[CodeWrite {
+                pc: 0,
+                address: 4,
+                value: 99
+            }]
+        );
+
+        let mut c = Computer::new(&program);
+        c.watch_code(true);
+        let fault = IntcodeError::SelfModifying { pc: 4, address: 4 };
+        assert_eq!(c.try_run(), Err(fault.clone()));
+        assert_eq!(c.try_run(), Err(fault));
+        assert_eq!(c.program_counter(), 4);
+    }
+
+    #[test]
+    fn patched_earlier() {
+        // increments its own first operand
+        let program = [1001, 1, 1, 1, 99];
+
+        let mut c = Computer::new(&program);
+        c.watch_code(false);
+        assert_eq!(c.try_run(), Ok(ComputerStatus::Halt));
+        assert_eq!(
+            c.code_writes(),
+            &[CodeWrite {
+                pc: 0,
+                address: 1,
+                value: 2
+            }]
+        );
+
+        let mut c = Computer::new(&program);
+        c.watch_code(true);
+        assert_eq!(
+            c.try_run(),
+            Err(IntcodeError::SelfModifying { pc: 0, address: 1 })
+        );
+        assert_eq!(c.memory()[1], 1);
+    }
+
+    #[test]
+    fn data_writes() {
+        let program = [
+            3, 21, 1008, 21, 8, 20, 1005, 20, 22, 107, 8, 21, 20, 1006, 20, 31, 1106, 0, 36, 98, 0,
+            0, 1002, 21, 125, 20, 4, 20, 1105, 1, 46, 104, 999, 1105, 1, 46, 1101, 1000, 1, 20, 4,
+            20, 1105, 1, 46, 98, 99,
+        ];
+
+        for input in 7..=9 {
+            let mut c = Computer::new(&program);
+            c.watch_code(true);
+            c.input_add(input);
+            while c.try_run() == Ok(ComputerStatus::ReturnedValue) {}
+            assert!(c.halted);
+            assert!(c.code_writes().is_empty());
+        }
+    }
+}
@@ -0,0 +1,21 @@
+use num::{CheckedAdd, CheckedMul, FromPrimitive, Num, ToPrimitive};
+use std::fmt::{Debug, Display};
+
+/// A value the VM can hold in a memory cell, e.g. `i64`, `i128` or `num::BigInt`
+pub trait Word:
+    Num + CheckedAdd + CheckedMul + ToPrimitive + FromPrimitive + PartialOrd + Clone + Debug + Display
+{
+}
+
+impl<T> Word for T where
+    T: Num
+        + CheckedAdd
+        + CheckedMul
+        + ToPrimitive
+        + FromPrimitive
+        + PartialOrd
+        + Clone
+        + Debug
+        + Display
+{
+}